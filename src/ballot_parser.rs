@@ -3,35 +3,277 @@ use std::collections::hash_map::{Entry, HashMap};
 use std::collections::HashSet;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{stdin, BufRead, BufReader, Read};
+use std::io::{self, stdin, BufRead, BufReader, Read, Write};
+use std::mem;
 use std::result::Result;
 use std::str::FromStr;
 use vote::traits::Weight;
 
+mod abif;
+mod blt;
+mod csv;
+mod debian;
+mod error;
+mod json;
+mod preflib;
+
+use self::csv::CsvLayout;
+pub use self::error::{Error, ErrorKind};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BallotType {
+    Ranked,
+    Approval,
+    Score,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Line,
+    Blt,
+    PrefLib,
+    Abif,
+    CsvRanks,
+    CsvChoices,
+    DebianVote,
+    DebianTally,
+    Json,
+}
+
+impl Format {
+    pub fn from_filename(filename: &str) -> Format {
+        let filename = filename.to_lowercase();
+        if filename.ends_with(".blt") {
+            Format::Blt
+        } else if [".soc", ".soi", ".toc", ".toi"]
+            .iter()
+            .any(|extension| filename.ends_with(extension))
+        {
+            Format::PrefLib
+        } else if filename.ends_with(".abif") {
+            Format::Abif
+        } else if filename.ends_with(".csv") {
+            Format::CsvRanks
+        } else if filename.ends_with(".json") {
+            Format::Json
+        } else {
+            Format::Line
+        }
+    }
+}
+
+pub type Scores<W> = Box<[(usize, W)]>;
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..b.len() + 1).collect::<Vec<_>>();
+    for (i, ch) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for j in 0..b.len() {
+            let above = row[j + 1];
+            row[j + 1] = if ch == b[j] {
+                diagonal
+            } else {
+                1 + diagonal.min(row[j]).min(above)
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// Groups (rank, candidate) pairs by rank, lowest first.
+fn group_ranks(mut ranks: Vec<(usize, usize)>) -> Box<[Box<[usize]>]> {
+    ranks.sort();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, &(rank, c)) in ranks.iter().enumerate() {
+        if i > 0 && ranks[i - 1].0 == rank {
+            groups.last_mut().unwrap().push(c);
+        } else {
+            groups.push(vec![c]);
+        }
+    }
+    groups
+        .into_iter()
+        .map(|group| group.into_boxed_slice())
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+// The first sep outside double-quoted names.
+fn find_unquoted(s: &str, sep: char) -> Option<usize> {
+    let (mut quoted, mut escaped) = (false, false);
+    for (i, ch) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted {
+            match ch {
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {}
+            }
+        } else if ch == '"' {
+            quoted = true;
+        } else if ch == sep {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn split_unquoted(mut s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    while let Some(i) = find_unquoted(s, sep) {
+        parts.push(&s[..i]);
+        s = &s[i + sep.len_utf8()..];
+    }
+    parts.push(s);
+    parts
+}
+
+fn strip_comment(line: &str) -> &str {
+    match find_unquoted(line, '#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+// A name in the line format: trimmed, or double-quoted with \" \\ \n and \t
+// escapes, keeping any other characters.
+fn unquote(text: &str) -> Result<String, ErrorKind> {
+    let text = text.trim();
+    let mut chars = match text.strip_prefix('"') {
+        Some(rest) => rest.chars(),
+        None if text.contains('"') => return Err(ErrorKind::UnexpectedQuote(text.to_string())),
+        None => return Ok(text.to_string()),
+    };
+    let mut name = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some('n') => name.push('\n'),
+                Some('t') => name.push('\t'),
+                Some(ch @ '"') | Some(ch @ '\\') => name.push(ch),
+                _ => return Err(ErrorKind::InvalidEscape(text.to_string())),
+            },
+            Some(ch) => name.push(ch),
+            None => {
+                return Err(ErrorKind::Unterminated {
+                    what: "quoted name",
+                    text: text.to_string(),
+                })
+            }
+        }
+    }
+    if !chars.as_str().trim().is_empty() {
+        return Err(ErrorKind::UnexpectedText {
+            after: "quoted name",
+            text: text.to_string(),
+        });
+    }
+    Ok(name)
+}
+
+// Quotes names that would not read back as they are.
+fn quote(name: &str) -> String {
+    let special = |ch: char| ">=:,#\"\\".contains(ch) || ch.is_control();
+    if !name.is_empty() && name.trim() == name && !name.contains(special) {
+        return name.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for ch in name.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// An error about part of a line.
+type LineError<'a> = (&'a str, ErrorKind);
+
+// A #NAME: directive, as the name and the rest of the line.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let directive = line.trim().strip_prefix('#')?;
+    let i = directive.find(':')?;
+    Some((directive[..i].trim(), strip_comment(&directive[i + 1..])))
+}
+
+fn join_names<'a, I: Iterator<Item = &'a String>>(names: I, separator: &str) -> String {
+    names.map(|name| quote(name)).collect::<Vec<_>>().join(separator)
+}
+
 pub struct BallotParser<W> {
+    pub ballot_type: BallotType,
     pub candidates: Vec<String>,
     pub candidate_index: HashMap<String, usize>,
     pub ballots: Vec<(Box<[Box<[usize]>]>, W)>,
+    // Approval and score ballots; an approval scores one.  The implied
+    // rankings are in ballots as well.
+    pub scores: Vec<(Scores<W>, W)>,
+    // From the file, if it says.
+    pub num_seats: Option<usize>,
+    pub title: Option<String>,
+    pub method: Option<String>,
+    // Removed from every ballot once all files are read.
+    pub withdrawn: Vec<String>,
+    // Reports of invalid lines in the line format.  Unless skip_invalid is
+    // set, any are errors and parse_ballot_files fails.
+    pub errors: Vec<Error>,
+    // How many of the errors are in directives rather than ballots.
+    pub directive_errors: usize,
+    // Once candidates are declared, other names are errors.
+    declared: bool,
+    // Stop after this many invalid lines; 0 for no limit.
+    max_errors: usize,
+    skip_invalid: bool,
 }
 
 impl<W: FromStr + Weight> BallotParser<W>
 where
     W::Err: Display,
 {
-    fn new() -> BallotParser<W> {
+    fn new(ballot_type: BallotType, max_errors: usize, skip_invalid: bool) -> BallotParser<W> {
         BallotParser {
+            ballot_type,
             candidates: Vec::new(),
             candidate_index: HashMap::new(),
             ballots: Vec::new(),
+            scores: Vec::new(),
+            num_seats: None,
+            title: None,
+            method: None,
+            withdrawn: Vec::new(),
+            errors: Vec::new(),
+            directive_errors: 0,
+            declared: false,
+            max_errors,
+            skip_invalid,
         }
     }
 
-    fn parse_candidate(&mut self, name: &str, used: &mut HashSet<usize>) -> Result<usize, String> {
-        let name = name.trim();
-        if name.is_empty() {
-            Err("empty candidate name")?
+    fn set_num_seats(&mut self, num_seats: usize) -> Result<(), ErrorKind> {
+        match self.num_seats {
+            Some(earlier) if earlier != num_seats => Err(ErrorKind::SeatsMismatch {
+                seats: num_seats,
+                earlier,
+            }),
+            _ => {
+                self.num_seats = Some(num_seats);
+                Ok(())
+            }
         }
-        let n = match self.candidate_index.entry(name.to_string()) {
+    }
+
+    fn candidate(&mut self, name: &str) -> usize {
+        match self.candidate_index.entry(name.to_string()) {
             Entry::Occupied(e) => *e.get(),
             Entry::Vacant(e) => {
                 let n = self.candidates.len();
@@ -39,96 +281,617 @@ where
                 self.candidates.push(name.to_string());
                 n
             }
-        };
+        }
+    }
+
+    // The closest declared candidate name, if any is close enough.
+    fn suggestion(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.candidates
+            .iter()
+            .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+            .filter(|&(distance, _)| 3 * distance <= name.chars().count() + 3)
+            .min()
+            .map(|(_, candidate)| &candidate[..])
+    }
+
+    fn parse_candidate(
+        &mut self,
+        name: &str,
+        used: &mut HashSet<usize>,
+    ) -> Result<usize, ErrorKind> {
+        self.known_candidate(name.trim(), used)
+    }
+
+    // A possibly quoted name in the line format.
+    fn parse_name<'a>(
+        &mut self,
+        text: &'a str,
+        used: &mut HashSet<usize>,
+    ) -> Result<usize, LineError<'a>> {
+        let name = unquote(text).map_err(|e| (text.trim(), e))?;
+        self.known_candidate(&name, used)
+            .map_err(|e| (text.trim(), e))
+    }
+
+    fn known_candidate(
+        &mut self,
+        name: &str,
+        used: &mut HashSet<usize>,
+    ) -> Result<usize, ErrorKind> {
+        if name.is_empty() {
+            return Err(ErrorKind::EmptyCandidateName);
+        }
+        if self.declared && !self.candidate_index.contains_key(name) {
+            return Err(ErrorKind::UnknownCandidate {
+                name: name.to_string(),
+                suggestion: self.suggestion(name).map(|s| s.to_string()),
+            });
+        }
+        let n = self.candidate(name);
         if used.insert(n) {
             Ok(n)
         } else {
-            Err(format!("candidate repeated: {}", name))
+            Err(ErrorKind::RepeatedCandidate(name.to_string()))
         }
     }
 
-    fn parse_group(
+    fn parse_group<'a>(
         &mut self,
-        group: &str,
+        group: &'a str,
         used: &mut HashSet<usize>,
-    ) -> Result<Box<[usize]>, String> {
+    ) -> Result<Box<[usize]>, LineError<'a>> {
         Ok(
-            group
-                .split('=')
-                .map(|name| self.parse_candidate(name, used))
+            split_unquoted(group, '=')
+                .into_iter()
+                .map(|name| self.parse_name(name, used))
                 .collect::<Result<Vec<_>, _>>()?
                 .into_boxed_slice(),
         )
     }
 
-    fn parse_groups(
+    fn parse_groups<'a>(
         &mut self,
-        groups: &str,
+        groups: &'a str,
         used: &mut HashSet<usize>,
-    ) -> Result<Box<[Box<[usize]>]>, String> {
+    ) -> Result<Box<[Box<[usize]>]>, LineError<'a>> {
+        if groups.trim().is_empty() {
+            return Ok(Box::new([]));
+        }
         Ok(
-            groups
-                .split('>')
+            split_unquoted(groups, '>')
+                .into_iter()
                 .map(|group| self.parse_group(group, used))
                 .collect::<Result<Vec<_>, _>>()?
                 .into_boxed_slice(),
         )
     }
 
-    fn add_ballot(&mut self, line: &str) -> Result<(), String> {
-        let line = line.trim();
+    fn parse_approvals<'a>(
+        &mut self,
+        approvals: &'a str,
+        used: &mut HashSet<usize>,
+    ) -> Result<Scores<W>, LineError<'a>> {
+        if approvals.trim().is_empty() {
+            return Ok(Box::new([]));
+        }
+        Ok(
+            split_unquoted(approvals, ',')
+                .into_iter()
+                .map(|name| Ok((self.parse_name(name, used)?, W::one())))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_boxed_slice(),
+        )
+    }
+
+    // NAME=SCORE pairs separated by whitespace or commas; names may contain
+    // whitespace, so each score ends at the first separator after it.
+    fn parse_scores<'a>(
+        &mut self,
+        scores: &'a str,
+        used: &mut HashSet<usize>,
+    ) -> Result<Scores<W>, LineError<'a>> {
+        if scores.trim().is_empty() {
+            return Ok(Box::new([]));
+        }
+        let parts = split_unquoted(scores, '=');
+        if parts.len() == 1 {
+            let name = parts[0].trim();
+            return Err((name, ErrorKind::MissingScore(name.to_string())));
+        }
+        let mut name = parts[0];
+        let mut result = Vec::with_capacity(parts.len() - 1);
+        for (i, part) in parts.iter().enumerate().skip(1) {
+            let part = part.trim_start();
+            let (text, rest) = if i + 1 == parts.len() {
+                (part.trim_end(), "")
+            } else {
+                match part.find(|ch: char| ch.is_whitespace() || ch == ',') {
+                    Some(j) => (&part[..j], &part[j..]),
+                    None => return Err((part, ErrorKind::EmptyCandidateName)),
+                }
+            };
+            let c = self.parse_name(name, used)?;
+            let score = W::from_str(text).map_err(|e| {
+                let kind = ErrorKind::InvalidScore {
+                    candidate: name.trim().to_string(),
+                    reason: e.to_string(),
+                };
+                (text, kind)
+            })?;
+            if score < W::zero() {
+                return Err((text, ErrorKind::NegativeScore(name.trim().to_string())));
+            }
+            result.push((c, score));
+            name = rest.trim_start_matches(|ch: char| ch.is_whitespace() || ch == ',');
+        }
+        Ok(result.into_boxed_slice())
+    }
+
+    // Candidates scored zero rank last, together with unscored candidates.
+    fn implied_ranking(scores: &[(usize, W)]) -> Box<[Box<[usize]>]> {
+        let mut scores = scores
+            .iter()
+            .filter(|(_, score)| *score > W::zero())
+            .collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.1.cmp(&a.1));
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut last: Option<&W> = None;
+        for &&(c, ref score) in &scores {
+            match last {
+                Some(last) if last.fuzzy_eq(score) => groups.last_mut().unwrap().push(c),
+                _ => groups.push(vec![c]),
+            }
+            last = Some(score);
+        }
+        groups
+            .into_iter()
+            .map(|group| group.into_boxed_slice())
+            .collect::<Vec<_>>()
+            .into_boxed_slice()
+    }
+
+    fn declare_candidates<'a>(&mut self, names: &'a str) -> Result<(), LineError<'a>> {
+        let used = &mut HashSet::new();
+        for text in split_unquoted(names, ',') {
+            let name = unquote(text).map_err(|e| (text.trim(), e))?;
+            if name.is_empty() {
+                return Err((text.trim(), ErrorKind::EmptyCandidateName));
+            }
+            if !used.insert(self.candidate(&name)) {
+                return Err((text.trim(), ErrorKind::RepeatedCandidate(name)));
+            }
+        }
+        self.declared = true;
+        Ok(())
+    }
+
+    fn withdraw<'a>(&mut self, names: &'a str) -> Result<(), LineError<'a>> {
+        let used = &mut HashSet::new();
+        let withdrawn = split_unquoted(names, ',')
+            .into_iter()
+            .map(|name| self.parse_name(name, used))
+            .collect::<Result<Vec<_>, _>>()?;
+        for c in withdrawn {
+            if !self.withdrawn.contains(&self.candidates[c]) {
+                let name = self.candidates[c].clone();
+                self.withdrawn.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_withdrawn(&mut self) {
+        if self.withdrawn.is_empty() {
+            return;
+        }
+        let candidates = mem::take(&mut self.candidates);
+        self.candidate_index.clear();
+        let mut index = Vec::with_capacity(candidates.len());
+        for name in &candidates {
+            index.push(if self.withdrawn.contains(name) {
+                None
+            } else {
+                Some(self.candidate(name))
+            });
+        }
+        for (groups, _) in &mut self.ballots {
+            *groups = groups
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .filter_map(|&c| index[c])
+                        .collect::<Vec<_>>()
+                        .into_boxed_slice()
+                })
+                .filter(|group| !group.is_empty())
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+        }
+        for (scores, _) in &mut self.scores {
+            *scores = scores
+                .iter()
+                .filter_map(|(c, score)| index[*c].map(|c| (c, score.clone())))
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+        }
+    }
+
+    fn add_ballot<'a>(&mut self, line: &'a str) -> Result<(), LineError<'a>> {
+        // #candidates: NAME, ... fixes the candidates up front, and
+        // #withdrawn: NAME, ... removes candidates from every ballot.  Other
+        // lines starting with # are comments, as is anything after a #.
+        match directive(line) {
+            Some(("candidates", names)) => return self.declare_candidates(names),
+            Some(("withdrawn", names)) => return self.withdraw(names),
+            _ => {}
+        }
+
+        let line = strip_comment(line).trim();
         if line.is_empty() {
             return Ok(());
         }
 
-        let (w, groups) = match line.find(':') {
+        let (w, groups) = match find_unquoted(line, ':') {
             Some(i) => {
-                let w = W::from_str(line[..i].trim())
-                    .map_err(|e| format!("cannot parse ballot weight: {}", e))?;
+                let weight = line[..i].trim();
+                let w = W::from_str(weight)
+                    .map_err(|e| (weight, ErrorKind::InvalidWeight(e.to_string())))?;
                 if w <= W::zero() {
-                    Err("non-positive ballot weight")?
+                    return Err((weight, ErrorKind::NonPositiveWeight));
                 }
                 (w, &line[i + 1..])
             }
             None => (W::one(), &line[..]),
         };
 
-        let ballot = (self.parse_groups(groups, &mut HashSet::new())?, w);
-        self.ballots.push(ballot);
+        let used = &mut HashSet::new();
+        let scores = match self.ballot_type {
+            BallotType::Ranked => {
+                let ballot = (self.parse_groups(groups, used)?, w);
+                self.ballots.push(ballot);
+                return Ok(());
+            }
+            BallotType::Approval => self.parse_approvals(groups, used)?,
+            BallotType::Score => self.parse_scores(groups, used)?,
+        };
+        self.ballots.push((Self::implied_ranking(&scores), w.clone()));
+        self.scores.push((scores, w));
         Ok(())
     }
 
-    fn add_ballots<R: Read>(&mut self, buf: BufReader<R>) -> Result<(), (usize, String)> {
+    // Keeps every invalid line in errors, up to max_errors.
+    fn add_ballots<R: Read>(&mut self, filename: &str, buf: BufReader<R>) -> Result<(), Error> {
         for (lineno, line) in buf.lines().enumerate() {
-            self.add_ballot(&line.map_err(|e| (lineno, e.to_string()))?)
-                .map_err(|e| (lineno, e))?;
+            let line = line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?;
+            let num_candidates = self.candidates.len();
+            if let Err((text, kind)) = self.add_ballot(&line) {
+                // Forget the candidates only the invalid line named.
+                for name in self.candidates.drain(num_candidates..) {
+                    self.candidate_index.remove(&name);
+                }
+                let before = &line[..text.as_ptr() as usize - line.as_ptr() as usize];
+                let mut error = Error::at_line(lineno, kind);
+                error.span.file = filename.to_string();
+                error.span.column = Some(before.chars().count() + 1);
+                error.span.width = text.chars().count();
+                error.span.source = Some(line.clone());
+                self.errors.push(error);
+                if directive(&line).is_some() {
+                    self.directive_errors += 1;
+                }
+                if self.max_errors != 0 && self.errors.len() >= self.max_errors {
+                    return Err(Error::at_line(
+                        lineno,
+                        ErrorKind::TooManyErrors(self.max_errors),
+                    ));
+                }
+            }
         }
         Ok(())
     }
 
-    fn add_ballot_file(&mut self, filename: &str) -> Result<(), String> {
+    fn add_ballot_file(&mut self, filename: &str, format: Option<Format>) -> Result<(), Error> {
         let file: Box<Read> = if filename == "-" {
             Box::new(stdin())
         } else {
-            Box::new(File::open(filename)
-                .map_err(|e| format!("error: {}: {}", filename, e))?)
+            Box::new(File::open(filename).map_err(|e| {
+                let mut error = Error::new(ErrorKind::Io(e));
+                error.span.file = filename.to_string();
+                error
+            })?)
         };
-        self.add_ballots(BufReader::new(file))
-            .map_err(|(lineno, e)| {
-                format!("{}:{}: error: {}", filename, lineno + 1, e)
-            })
+        let buf = BufReader::new(file);
+        match format.unwrap_or_else(|| Format::from_filename(filename)) {
+            Format::Line => self.add_ballots(filename, buf),
+            Format::Blt => self.add_blt(buf),
+            Format::PrefLib => self.add_preflib(buf),
+            Format::Abif => self.add_abif(buf),
+            Format::CsvRanks => self.add_csv(buf, CsvLayout::Ranks),
+            Format::CsvChoices => self.add_csv(buf, CsvLayout::Choices),
+            Format::DebianVote => self.add_debian_vote(buf),
+            Format::DebianTally => self.add_debian_tally(buf),
+            Format::Json => self.add_json(buf),
+        }
+        .map_err(|mut e| {
+            e.span.file = filename.to_string();
+            e
+        })
+    }
+}
+
+impl<W: Display + Weight> BallotParser<W> {
+    // The line format, with directives for candidates the ballots do not
+    // name.  Any title, number of seats or method is left out.
+    fn write_line<Out: Write>(&self, out: &mut Out) -> io::Result<()> {
+        // Withdrawn candidates are already off the ballots, so they are
+        // declared again to withdraw them once more.
+        if !self.candidates.is_empty() || !self.withdrawn.is_empty() {
+            let candidates = self.candidates.iter().chain(&self.withdrawn);
+            writeln!(out, "#candidates: {}", join_names(candidates, ", "))?;
+        }
+        if !self.withdrawn.is_empty() {
+            writeln!(out, "#withdrawn: {}", join_names(self.withdrawn.iter(), ", "))?;
+        }
+
+        let name = |c: usize| &self.candidates[c];
+        let ballots = match self.ballot_type {
+            BallotType::Ranked => self
+                .ballots
+                .iter()
+                .map(|(groups, w)| {
+                    let groups = groups
+                        .iter()
+                        .map(|group| join_names(group.iter().map(|&c| name(c)), " = "))
+                        .collect::<Vec<_>>();
+                    (groups.join(" > "), w)
+                })
+                .collect::<Vec<_>>(),
+            BallotType::Approval => self
+                .scores
+                .iter()
+                .map(|(scores, w)| (join_names(scores.iter().map(|&(c, _)| name(c)), ", "), w))
+                .collect(),
+            BallotType::Score => self
+                .scores
+                .iter()
+                .map(|(scores, w)| {
+                    let scores = scores
+                        .iter()
+                        .map(|(c, score)| format!("{}={}", quote(name(*c)), score))
+                        .collect::<Vec<_>>();
+                    (scores.join(" "), w)
+                })
+                .collect(),
+        };
+        for (ballot, w) in ballots {
+            if ballot.is_empty() {
+                writeln!(out, "{}:", w)?;
+            } else {
+                writeln!(out, "{}: {}", w, ballot)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_ballots<Out: Write>(&self, format: Format, out: &mut Out) -> io::Result<()> {
+        match format {
+            Format::Line => self.write_line(out),
+            Format::Abif => self.write_abif(out),
+            Format::Blt
+            | Format::PrefLib
+            | Format::CsvRanks
+            | Format::CsvChoices
+            | Format::DebianVote
+            | Format::DebianTally
+            | Format::Json => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot write ballots in this format",
+            )),
+        }
     }
 }
 
-pub fn parse_ballot_files<W, Str>(filenames: &[Str]) -> Result<BallotParser<W>, String>
+pub fn parse_ballot_files<W, Str>(
+    filenames: &[Str],
+    ballot_type: BallotType,
+    format: Option<Format>,
+    max_errors: usize,
+    skip_invalid: bool,
+) -> Result<BallotParser<W>, Vec<Error>>
 where
     W: FromStr + Weight,
     W::Err: Display,
     Str: Borrow<str>,
 {
-    let mut bp = BallotParser::new();
+    let mut bp = BallotParser::new(ballot_type, max_errors, skip_invalid);
     for filename in filenames {
-        bp.add_ballot_file(filename.borrow())?;
+        if let Err(e) = bp.add_ballot_file(filename.borrow(), format) {
+            bp.errors.push(e);
+            return Err(bp.errors);
+        }
+    }
+    if !bp.skip_invalid && !bp.errors.is_empty() {
+        return Err(bp.errors);
     }
+    bp.remove_withdrawn();
     Ok(bp)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use vote::hw_float::HwFloat;
+    use vote::traits::Weight;
+
+    use super::{BallotParser, BallotType, Error};
+
+    pub fn parser(ballot_type: BallotType) -> BallotParser<HwFloat> {
+        BallotParser::new(ballot_type, 0, false)
+    }
+
+    pub fn input(text: &str) -> BufReader<&[u8]> {
+        BufReader::new(text.as_bytes())
+    }
+
+    // Each ranked ballot as WEIGHT: NAME > NAME = NAME.
+    pub fn ranked(bp: &BallotParser<HwFloat>) -> Vec<String> {
+        bp.ballots
+            .iter()
+            .map(|(groups, w)| {
+                let groups = groups
+                    .iter()
+                    .map(|group| {
+                        group
+                            .iter()
+                            .map(|&c| &bp.candidates[c][..])
+                            .collect::<Vec<_>>()
+                            .join(" = ")
+                    })
+                    .collect::<Vec<_>>();
+                format!("{}: {}", w, groups.join(" > "))
+            })
+            .collect()
+    }
+
+    // The line, counting from 1, and the message.
+    pub fn error(result: Result<(), Error>) -> (Option<usize>, String) {
+        let e = result.unwrap_err();
+        (e.span.line, e.kind.to_string())
+    }
+
+    #[test]
+    fn test_withdrawn() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "#candidates: A, B, C, D\n#withdrawn: B\n2: A > B = C > D\nB > D\n\
+                    #withdrawn: D, B\n";
+        bp.add_ballots("", input(text)).unwrap();
+        bp.remove_withdrawn();
+        assert_eq!(bp.candidates, ["A", "C"]);
+        assert_eq!(bp.candidate_index["C"], 1);
+        assert_eq!(bp.withdrawn, ["B", "D"]);
+        assert_eq!(ranked(&bp), ["2: A > C", "1: "]);
+
+        let mut bp = parser(BallotType::Score);
+        bp.add_ballots("", input("#withdrawn: A\nA=2 B=1\n")).unwrap();
+        bp.remove_withdrawn();
+        assert_eq!(bp.candidates, ["B"]);
+        assert_eq!(*bp.scores[0].0, [(0, HwFloat::from_i64(1))]);
+
+        let mut bp = parser(BallotType::Ranked);
+        bp.add_ballots("", input("#candidates: A\n#withdrawn: A\nA\n")).unwrap();
+        bp.remove_withdrawn();
+        assert!(bp.candidates.is_empty());
+
+        let mut bp = parser(BallotType::Ranked);
+        let text = "#candidates: Alice, Carol\n#withdrawn: Carol, Bob\n";
+        bp.add_ballots("", input(text)).unwrap();
+        assert_eq!(bp.errors[0].span.line, Some(2));
+        assert_eq!(bp.errors[0].span.column, Some(20));
+        assert_eq!(bp.errors[0].kind.to_string(), "unknown candidate: Bob");
+        // A directive with an invalid name withdraws nobody.
+        assert!(bp.withdrawn.is_empty());
+    }
+
+    // Writes the ballots in the line format and reads them back.
+    fn round_trip(bp: &BallotParser<HwFloat>) -> (String, BallotParser<HwFloat>) {
+        let mut out = Vec::new();
+        bp.write_line(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let mut reread = parser(bp.ballot_type);
+        reread.add_ballots("", input(&text)).unwrap();
+        assert!(reread.errors.is_empty(), "{:?}", reread.errors);
+        reread.remove_withdrawn();
+        (text, reread)
+    }
+
+    #[test]
+    fn test_write_line() {
+        let text = r##"#candidates: "a#b", "x > y", "p = q", "say \"hi\"", " padded ", back\slash
+            #candidates: "c:d, e", Unused
+            #withdrawn: "c:d, e"
+            #seats: 2
+            2.5: "a#b" > "x > y" = "p = q"  # comment
+            "say \"hi\"" > " padded " > "c:d, e" = back\slash
+            1:
+        "##;
+        let mut bp = parser(BallotType::Ranked);
+        bp.add_ballots("", input(text)).unwrap();
+        assert!(bp.errors.is_empty(), "{:?}", bp.errors);
+        assert_eq!(bp.num_seats, None);
+        bp.remove_withdrawn();
+        assert_eq!(
+            bp.candidates,
+            ["a#b", "x > y", "p = q", "say \"hi\"", " padded ", "back\\slash", "Unused"]
+        );
+        assert_eq!(
+            ranked(&bp),
+            [
+                "2.5: a#b > x > y = p = q",
+                "1: say \"hi\" >  padded  > back\\slash",
+                "1: ",
+            ]
+        );
+
+        let (written, reread) = round_trip(&bp);
+        assert_eq!(reread.candidates, bp.candidates);
+        assert_eq!(ranked(&reread), ranked(&bp));
+        assert_eq!(reread.withdrawn, bp.withdrawn);
+        assert_eq!(round_trip(&reread).0, written);
+
+        for &(ballot_type, text) in &[
+            (BallotType::Approval, "\"x, y\", \" z\"\n2: \"=\"\n"),
+            (BallotType::Score, "\"x=y\"=3 \" z \"=0.5, \"#\"=0\n"),
+        ] {
+            let mut bp = parser(ballot_type);
+            bp.add_ballots("", input(text)).unwrap();
+            let (_, reread) = round_trip(&bp);
+            assert_eq!(reread.candidates, bp.candidates);
+            assert_eq!(reread.scores, bp.scores);
+            assert_eq!(reread.ballots, bp.ballots);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let text = "A > B\n1: A > A\nx: B\n#withdrawn: B, B\n2: C > A\n0: D\n";
+        let mut bp: BallotParser<HwFloat> = BallotParser::new(BallotType::Ranked, 0, true);
+        bp.add_ballots("f", input(text)).unwrap();
+        let errors = bp
+            .errors
+            .iter()
+            .map(|e| (e.span.line, e.span.column, e.kind.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                (Some(2), Some(8), "candidate repeated: A".to_string()),
+                (Some(3), Some(1), "cannot parse ballot weight: invalid float literal".to_string()),
+                (Some(4), Some(16), "candidate repeated: B".to_string()),
+                (Some(6), Some(1), "non-positive ballot weight".to_string()),
+            ]
+        );
+        assert_eq!(bp.errors[0].span.file, "f");
+        assert_eq!(bp.directive_errors, 1);
+        // D only appeared on a skipped line.
+        assert_eq!(bp.candidates, ["A", "B", "C"]);
+        assert!(!bp.candidate_index.contains_key("D"));
+        assert_eq!(ranked(&bp), ["1: A > B", "2: C > A"]);
+        assert!(bp.withdrawn.is_empty());
+
+        // 0 is no limit.
+        let mut bp: BallotParser<HwFloat> = BallotParser::new(BallotType::Ranked, 0, false);
+        bp.add_ballots("f", input(&"x: A\n".repeat(30))).unwrap();
+        assert_eq!(bp.errors.len(), 30);
+        assert!(bp.candidates.is_empty());
+
+        let text = "1: A > A\nB\nx: B\nC\n0: D\n";
+        let mut bp: BallotParser<HwFloat> = BallotParser::new(BallotType::Ranked, 2, false);
+        let e = bp.add_ballots("f", input(text)).unwrap_err();
+        assert_eq!(e.span.line, Some(3));
+        assert_eq!(e.kind.to_string(), "stopping after 2 invalid lines");
+        assert_eq!(bp.errors.len(), 2);
+        assert_eq!(bp.candidates, ["B"]);
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{BallotParser, BallotType, Error, ErrorKind, Scores};
+
+// The Aggregated Ballot Information Format (ABIF) from electorama:
+//
+//   {"title": "Election title"}     metadata
+//   =DGM:[Doña García Márquez]      candidate token and full name
+//   =SY:"Steven Yang"
+//   # comment
+//   27:DGM>SBJ=SY                   count, then > and = as usual
+//   12:[Sue Ye]/5,DGM/2             ratings; , gives no preference
+//
+// Undeclared tokens are candidates named by the token.  Ranked ballots with
+// ratings and no > are ranked by the ratings.
+
+fn end_of_line(s: &str) -> bool {
+    let s = s.trim_start();
+    s.is_empty() || s.starts_with('#')
+}
+
+// s follows the opening quote.
+fn parse_quoted(s: &str) -> Result<(String, &str), ErrorKind> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((value, &s[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, ch)) => value.push(ch),
+                None => break,
+            },
+            _ => value.push(ch),
+        }
+    }
+    Err(ErrorKind::Unterminated {
+        what: "string",
+        text: format!("\"{}", s),
+    })
+}
+
+// Candidate tokens are [bracketed], "quoted" or bare.
+fn parse_token(s: &str) -> Result<(String, &str), ErrorKind> {
+    if let Some(rest) = s.strip_prefix('[') {
+        let end = rest.find(']').ok_or_else(|| ErrorKind::Unterminated {
+            what: "[",
+            text: s.to_string(),
+        })?;
+        Ok((rest[..end].to_string(), &rest[end + 1..]))
+    } else if let Some(rest) = s.strip_prefix('"') {
+        parse_quoted(rest)
+    } else {
+        let end = s
+            .find(|ch: char| ch.is_whitespace() || "#>=,/:[]{}\"".contains(ch))
+            .unwrap_or(s.len());
+        if end == 0 {
+            return Err(ErrorKind::Expected {
+                what: "candidate token",
+                before: s.to_string(),
+            });
+        }
+        Ok((s[..end].to_string(), &s[end..]))
+    }
+}
+
+// {"key": "value", key: value, ...}; only the title is used.
+fn parse_metadata(line: &str) -> Result<Vec<(String, String)>, ErrorKind> {
+    let mut rest = line[1..].trim_start();
+    let mut pairs = Vec::new();
+    while !rest.starts_with('}') {
+        let (key, after) = parse_token(rest)?;
+        let after = after.trim_start();
+        let after = after
+            .strip_prefix(':')
+            .ok_or_else(|| ErrorKind::Expected {
+                what: ":",
+                before: after.to_string(),
+            })?
+            .trim_start();
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            parse_quoted(quoted)?
+        } else {
+            let end = after.find(&[',', '}'][..]).ok_or(ErrorKind::Unterminated {
+                what: "metadata",
+                text: String::new(),
+            })?;
+            (after[..end].trim().to_string(), &after[end..])
+        };
+        pairs.push((key, value));
+        rest = after.trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        } else if !rest.starts_with('}') {
+            return Err(ErrorKind::Expected {
+                what: ", or }",
+                before: rest.to_string(),
+            });
+        }
+    }
+    if !end_of_line(&rest[1..]) {
+        return Err(ErrorKind::UnexpectedText {
+            after: "metadata",
+            text: rest[1..].to_string(),
+        });
+    }
+    Ok(pairs)
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for ch in s.chars() {
+        if ch == '"' || ch == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn bracket(name: &str) -> String {
+    if name.contains(']') {
+        quote(name)
+    } else {
+        format!("[{}]", name)
+    }
+}
+
+struct Preference<W> {
+    candidate: usize,
+    rating: Option<W>,
+    // The separator before this candidate: >, = or , (, for the first).
+    separator: char,
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    fn abif_candidate(
+        &mut self,
+        line: &str,
+        tokens: &mut HashMap<String, usize>,
+    ) -> Result<(), ErrorKind> {
+        let (token, rest) = parse_token(&line[1..])?;
+        let rest = rest.trim_start();
+        let (name, rest) = match rest.strip_prefix(':') {
+            Some(rest) => {
+                let rest = rest.trim_start();
+                if rest.starts_with('[') || rest.starts_with('"') {
+                    parse_token(rest)?
+                } else {
+                    let end = rest.find('#').unwrap_or(rest.len());
+                    (rest[..end].trim().to_string(), &rest[end..])
+                }
+            }
+            None => (token.clone(), rest),
+        };
+        if !end_of_line(rest) {
+            return Err(ErrorKind::UnexpectedText {
+                after: "candidate",
+                text: rest.to_string(),
+            });
+        }
+        if name.trim().is_empty() {
+            return Err(ErrorKind::EmptyCandidateName);
+        }
+        if tokens.contains_key(&token) {
+            return Err(ErrorKind::RepeatedCandidate(token));
+        }
+        let c = self.candidate(name.trim());
+        tokens.insert(token, c);
+        Ok(())
+    }
+
+    fn abif_preferences(
+        &mut self,
+        line: &str,
+        tokens: &HashMap<String, usize>,
+    ) -> Result<Vec<Preference<W>>, ErrorKind> {
+        let used = &mut HashSet::new();
+        let mut preferences = Vec::new();
+        let mut rest = line.trim_start();
+        let mut separator = ',';
+        while !end_of_line(rest) {
+            let (token, after) = parse_token(rest)?;
+            let candidate = match tokens.get(&token) {
+                Some(&c) => c,
+                None => self.candidate(&token),
+            };
+            if !used.insert(candidate) {
+                return Err(ErrorKind::RepeatedCandidate(token));
+            }
+            rest = after.trim_start();
+            let rating = match rest.strip_prefix('/') {
+                Some(after) => {
+                    let after = after.trim_start();
+                    let end = after
+                        .find(|ch: char| ch.is_whitespace() || "#>=,".contains(ch))
+                        .unwrap_or(after.len());
+                    let rating = W::from_str(&after[..end]).map_err(|e| {
+                        ErrorKind::InvalidScore {
+                            candidate: token.clone(),
+                            reason: e.to_string(),
+                        }
+                    })?;
+                    if rating < W::zero() {
+                        return Err(ErrorKind::NegativeScore(token));
+                    }
+                    rest = after[end..].trim_start();
+                    Some(rating)
+                }
+                None => None,
+            };
+            preferences.push(Preference {
+                candidate,
+                rating,
+                separator,
+            });
+            if end_of_line(rest) {
+                break;
+            }
+            separator = rest.chars().next().unwrap();
+            if !">=,".contains(separator) {
+                return Err(ErrorKind::Expected {
+                    what: ">, = or ,",
+                    before: rest.to_string(),
+                });
+            }
+            rest = rest[1..].trim_start();
+        }
+        Ok(preferences)
+    }
+
+    fn abif_ballot(
+        &mut self,
+        line: &str,
+        tokens: &HashMap<String, usize>,
+    ) -> Result<(), ErrorKind> {
+        let i = line
+            .find(&[':', '*'][..])
+            .ok_or(ErrorKind::Missing("count before ballot"))?;
+        let w = W::from_str(line[..i].trim())
+            .map_err(|e| ErrorKind::InvalidWeight(e.to_string()))?;
+        if w <= W::zero() {
+            return Err(ErrorKind::NonPositiveWeight);
+        }
+        let preferences = self.abif_preferences(&line[i + 1..], tokens)?;
+
+        let rated = preferences.iter().any(|p| p.rating.is_some());
+        let scores: Scores<W> = match self.ballot_type {
+            BallotType::Ranked
+                if !rated || preferences.iter().any(|p| p.separator == '>') =>
+            {
+                let mut groups: Vec<Vec<usize>> = Vec::new();
+                for p in &preferences {
+                    if p.separator == '>' || groups.is_empty() {
+                        groups.push(vec![p.candidate]);
+                    } else {
+                        groups.last_mut().unwrap().push(p.candidate);
+                    }
+                }
+                let groups = groups
+                    .into_iter()
+                    .map(|group| group.into_boxed_slice())
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice();
+                self.ballots.push((groups, w));
+                return Ok(());
+            }
+            BallotType::Approval => preferences
+                .into_iter()
+                .filter(|p| p.rating.as_ref().map(|r| !r.is_zero()).unwrap_or(true))
+                .map(|p| (p.candidate, W::one()))
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+            _ => preferences
+                .into_iter()
+                .map(|p| match p.rating {
+                    Some(rating) => Ok((p.candidate, rating)),
+                    None => Err(ErrorKind::MissingScore(
+                        self.candidates[p.candidate].clone(),
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_boxed_slice(),
+        };
+        self.ballots.push((Self::implied_ranking(&scores), w.clone()));
+        if self.ballot_type != BallotType::Ranked {
+            self.scores.push((scores, w));
+        }
+        Ok(())
+    }
+
+    pub(super) fn add_abif<R: Read>(&mut self, buf: BufReader<R>) -> Result<(), Error> {
+        let mut tokens = HashMap::new();
+        for (lineno, line) in buf.lines().enumerate() {
+            let line = line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?;
+            let line = line.trim();
+            if end_of_line(line) {
+                continue;
+            }
+            if line.starts_with('{') {
+                let metadata = parse_metadata(line).map_err(|e| Error::at_line(lineno, e))?;
+                for (key, value) in metadata {
+                    if key == "title" && self.title.is_none() {
+                        self.title = Some(value);
+                    }
+                }
+            } else if line.starts_with('=') {
+                self.abif_candidate(line, &mut tokens)
+                    .map_err(|e| Error::at_line(lineno, e))?;
+            } else {
+                self.abif_ballot(line, &tokens)
+                    .map_err(|e| Error::at_line(lineno, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Display + Weight> BallotParser<W> {
+    // Tokens are the alphanumeric characters of each name, made unique.
+    fn abif_tokens(&self) -> Vec<String> {
+        let mut used = HashSet::new();
+        self.candidates
+            .iter()
+            .enumerate()
+            .map(|(c, name)| {
+                let mut base = name.chars().filter(|ch| ch.is_alphanumeric()).collect::<String>();
+                if base.is_empty() {
+                    base = format!("C{}", c + 1);
+                }
+                let mut token = base.clone();
+                let mut i = 1;
+                while !used.insert(token.clone()) {
+                    i += 1;
+                    token = format!("{}_{}", base, i);
+                }
+                token
+            })
+            .collect()
+    }
+
+    // ABIF counts and ratings are whole numbers.
+    pub(super) fn write_abif<Out: Write>(&self, out: &mut Out) -> io::Result<()> {
+        let weights = self.ballots.iter().map(|(_, w)| w);
+        let ratings = self.scores.iter().flat_map(|(scores, _)| scores.iter().map(|(_, s)| s));
+        if let Some(n) = weights.chain(ratings).find(|&n| n.floor() != *n) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("ABIF counts and ratings must be whole numbers: {}", n),
+            ));
+        }
+        writeln!(out, "{{\"version\": \"0.1\"}}")?;
+        if let Some(ref title) = self.title {
+            writeln!(out, "{{\"title\": {}}}", quote(title))?;
+        }
+        let tokens = self.abif_tokens();
+        for (token, name) in tokens.iter().zip(&self.candidates) {
+            writeln!(out, "={}:{}", token, bracket(name))?;
+        }
+        for (scores, w) in &self.scores {
+            let preferences = scores
+                .iter()
+                .map(|(c, score)| format!("{}/{}", tokens[*c], score))
+                .collect::<Vec<_>>();
+            writeln!(out, "{}:{}", w, preferences.join(","))?;
+        }
+        for (groups, w) in self.ballots.iter().filter(|_| self.scores.is_empty()) {
+            let groups = groups
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|&c| &tokens[c][..])
+                        .collect::<Vec<_>>()
+                        .join("=")
+                })
+                .collect::<Vec<_>>();
+            writeln!(out, "{}:{}", w, groups.join(">"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+    use vote::hw_float::HwFloat;
+    use vote::traits::Weight;
+
+    #[test]
+    fn test_abif() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "{\"title\": \"Title\"}\n=DGM:[Doña García Márquez]\n\
+                    =SY:\"Steven \\\"Y\\\"\"\n# comment\n27:DGM>SBJ=SY\n\
+                    12:SY/5,DGM/2 # ranked by rating\n";
+        bp.add_abif(input(text)).unwrap();
+        assert_eq!(
+            bp.candidates,
+            ["Doña García Márquez", "Steven \"Y\"", "SBJ"]
+        );
+        assert_eq!(
+            ranked(&bp),
+            [
+                "27: Doña García Márquez > SBJ = Steven \"Y\"",
+                "12: Steven \"Y\" > Doña García Márquez",
+            ]
+        );
+        assert_eq!(bp.title, Some("Title".to_string()));
+
+        let mut bp = parser(BallotType::Score);
+        bp.add_abif(input("=A:[Alice]\n2:A/5,B/0\n")).unwrap();
+        assert_eq!(
+            *bp.scores[0].0,
+            [(0, HwFloat::from_i64(5)), (1, HwFloat::from_i64(0))]
+        );
+    }
+
+    #[test]
+    fn test_abif_errors() {
+        let bad = |ballot: &str| {
+            let text = format!("=A:[Alice]\n1:A\n{}\n", ballot);
+            error(parser(BallotType::Ranked).add_abif(input(&text)))
+        };
+        assert_eq!(
+            bad("1:A>[Alice]"),
+            (Some(3), "candidate repeated: Alice".to_string())
+        );
+        assert_eq!(
+            bad("x:A"),
+            (
+                Some(3),
+                "cannot parse ballot weight: invalid float literal".to_string()
+            )
+        );
+        assert_eq!(
+            bad("=A:Ann"),
+            (Some(3), "candidate repeated: A".to_string())
+        );
+        assert_eq!(
+            bad("1:A B"),
+            (Some(3), "expected >, = or , before B".to_string())
+        );
+        assert_eq!(
+            bad("A>B"),
+            (Some(3), "missing count before ballot".to_string())
+        );
+    }
+
+    #[test]
+    fn test_write_abif() {
+        let round_trip = |ballot_type: BallotType, text: &str| {
+            let mut bp = parser(ballot_type);
+            bp.add_abif(input(text)).unwrap();
+            let mut out = Vec::new();
+            bp.write_abif(&mut out).unwrap();
+            let mut reread = parser(ballot_type);
+            reread
+                .add_abif(input(&String::from_utf8(out).unwrap()))
+                .unwrap();
+            assert_eq!(reread.candidates, bp.candidates);
+            assert_eq!(ranked(&reread), ranked(&bp));
+            assert_eq!(reread.scores, bp.scores);
+            assert_eq!(reread.title, bp.title);
+            bp
+        };
+        round_trip(
+            BallotType::Ranked,
+            "{\"title\": \"Title\"}\n=A:[Alice]\n=B:\"Bob [2]\"\n3:A>B=C\n1:C\n",
+        );
+        round_trip(
+            BallotType::Score,
+            "=A:[Alice]\n=B:\"Bob [2]\"\n2:A/5,B/0\n1:B/3,C/1\n",
+        );
+
+        // Tokens come from the letters and digits in each name, made unique.
+        let bp = round_trip(
+            BallotType::Ranked,
+            "=T1:[A B]\n=T2:[AB]\n=T3:[AB_2]\n=T4:[AB2]\n=T5:[C6]\n=T6:[+++]\n\
+             =T7:\"x]y\"\n2:T1>T2=T3>T4\n1:T5>T6>T7\n",
+        );
+        assert_eq!(
+            bp.abif_tokens(),
+            ["AB", "AB_2", "AB2", "AB2_2", "C6", "C6_2", "xy"]
+        );
+
+        let mut bp = parser(BallotType::Score);
+        bp.add_abif(input("1.5:A/2,B/1\n")).unwrap();
+        let e = bp.write_abif(&mut Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "ABIF counts and ratings must be whole numbers: 1.5"
+        );
+    }
+}
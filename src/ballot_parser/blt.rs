@@ -0,0 +1,326 @@
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{BallotParser, BallotType, Error, ErrorKind};
+
+// The BLT format used by OpenSTV and OpaVote:
+//
+//   4 2                   number of candidates and seats
+//   -4                    withdrawn candidates (optional)
+//   3 1 2 0               weight, then candidate numbers, ending in 0
+//   1 2=3 1 0             = gives equal preferences
+//   0                     end of ballots
+//   "Alice"               candidate names, in order
+//   "Bob"
+//   "Carol"
+//   "Dave"
+//   "Election title"      (optional)
+
+#[derive(PartialEq)]
+enum Section {
+    Header,
+    Ballots,
+    Names,
+    Title,
+    Done,
+}
+
+struct BltReader<W> {
+    section: Section,
+    num_candidates: usize,
+    num_seats: usize,
+    withdrawn: Vec<bool>,
+    ballots: Vec<(Vec<Vec<usize>>, W)>,
+    names: Vec<String>,
+    title: Option<String>,
+}
+
+fn parse_count(token: &str, what: &'static str) -> Result<usize, ErrorKind> {
+    token
+        .parse::<usize>()
+        .map_err(|e| ErrorKind::InvalidNumber {
+            what,
+            reason: e.to_string(),
+        })
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+// Names are normally quoted, possibly several to a line; an unquoted line is
+// one name.
+fn parse_strings(line: &str) -> Result<Vec<String>, ErrorKind> {
+    let mut line = line.trim();
+    if !line.starts_with('"') {
+        return Ok(vec![line.to_string()]);
+    }
+    let mut strings = Vec::new();
+    while line.starts_with('"') {
+        let end = line[1..].find('"').ok_or_else(|| ErrorKind::Unterminated {
+            what: "string",
+            text: line.to_string(),
+        })?;
+        strings.push(line[1..end + 1].to_string());
+        line = line[end + 2..].trim_start();
+    }
+    if !line.is_empty() {
+        return Err(ErrorKind::UnexpectedText {
+            after: "string",
+            text: line.to_string(),
+        });
+    }
+    Ok(strings)
+}
+
+impl<W: FromStr + Weight> BltReader<W>
+where
+    W::Err: Display,
+{
+    fn candidate(&self, token: &str) -> Result<usize, ErrorKind> {
+        match token.parse::<usize>() {
+            Ok(c) if c >= 1 && c <= self.num_candidates => Ok(c - 1),
+            _ => Err(ErrorKind::InvalidCandidateNumber(token.to_string())),
+        }
+    }
+
+    fn header(&mut self, line: &str) -> Result<(), ErrorKind> {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() != 2 {
+            return Err(ErrorKind::FieldCount {
+                what: "numbers in header",
+                expected: 2,
+                found: tokens.len(),
+            });
+        }
+        self.num_candidates = parse_count(tokens[0], "candidates")?;
+        self.num_seats = parse_count(tokens[1], "seats")?;
+        self.withdrawn = vec![false; self.num_candidates];
+        self.section = Section::Ballots;
+        Ok(())
+    }
+
+    fn withdraw(&mut self, line: &str) -> Result<(), ErrorKind> {
+        for token in line.split_whitespace() {
+            if !token.starts_with('-') {
+                return Err(ErrorKind::InvalidCandidateNumber(token.to_string()));
+            }
+            let c = self.candidate(&token[1..])?;
+            self.withdrawn[c] = true;
+        }
+        Ok(())
+    }
+
+    fn ballot(&mut self, line: &str) -> Result<(), ErrorKind> {
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.peek().unwrap_or(&"").starts_with('(') {
+            tokens.next();
+        }
+        let w = match tokens.next() {
+            Some("0") => {
+                self.section = Section::Names;
+                return Ok(());
+            }
+            Some(token) if token.starts_with('-') && self.ballots.is_empty() => {
+                return self.withdraw(line);
+            }
+            Some(token) => W::from_str(token)
+                .map_err(|e| ErrorKind::InvalidWeight(e.to_string()))?,
+            None => return Ok(()),
+        };
+        if w <= W::zero() {
+            return Err(ErrorKind::NonPositiveWeight);
+        }
+
+        let mut used = vec![false; self.num_candidates];
+        let mut groups = Vec::new();
+        let mut terminated = false;
+        for token in tokens {
+            if terminated {
+                return Err(ErrorKind::UnexpectedText {
+                    after: "end of ballot",
+                    text: token.to_string(),
+                });
+            }
+            if token == "0" {
+                terminated = true;
+                continue;
+            }
+            let mut group = Vec::new();
+            for t in token.split('=') {
+                let c = self.candidate(t)?;
+                if used[c] {
+                    return Err(ErrorKind::RepeatedCandidate(t.to_string()));
+                }
+                used[c] = true;
+                group.push(c);
+            }
+            groups.push(group);
+        }
+        if !terminated {
+            return Err(ErrorKind::Missing("0 at end of ballot"));
+        }
+        self.ballots.push((groups, w));
+        Ok(())
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), ErrorKind> {
+        if self.section == Section::Header || self.section == Section::Ballots {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                return Ok(());
+            }
+            return if self.section == Section::Header {
+                self.header(line)
+            } else {
+                self.ballot(line)
+            };
+        }
+
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        for string in parse_strings(line)? {
+            match self.section {
+                Section::Names => {
+                    self.names.push(string);
+                    if self.names.len() == self.num_candidates {
+                        self.section = Section::Title;
+                    }
+                }
+                Section::Title => {
+                    self.title = Some(string);
+                    self.section = Section::Done;
+                }
+                _ => {
+                    return Err(ErrorKind::UnexpectedText {
+                        after: "title",
+                        text: string,
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    pub(super) fn add_blt<R: Read>(&mut self, buf: BufReader<R>) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("BLT files")));
+        }
+        let mut reader = BltReader {
+            section: Section::Header,
+            num_candidates: 0,
+            num_seats: 0,
+            withdrawn: Vec::new(),
+            ballots: Vec::new(),
+            names: Vec::new(),
+            title: None,
+        };
+        let mut num_lines = 0;
+        for (lineno, line) in buf.lines().enumerate() {
+            reader
+                .line(&line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?)
+                .map_err(|e| Error::at_line(lineno, e))?;
+            num_lines = lineno + 1;
+        }
+        let missing = match reader.section {
+            Section::Header => Some(ErrorKind::Missing("header")),
+            Section::Ballots => Some(ErrorKind::Missing("end of ballots (0)")),
+            Section::Names if reader.names.len() < reader.num_candidates => {
+                Some(ErrorKind::FieldCount {
+                    what: "candidate names",
+                    expected: reader.num_candidates,
+                    found: reader.names.len(),
+                })
+            }
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            return Err(Error::at_line(num_lines, missing));
+        }
+
+        self.set_num_seats(reader.num_seats).map_err(Error::new)?;
+        if self.title.is_none() {
+            self.title = reader.title;
+        }
+
+        let index = reader
+            .names
+            .iter()
+            .map(|name| self.candidate(name))
+            .collect::<Vec<_>>();
+        for (&c, &withdrawn) in index.iter().zip(&reader.withdrawn) {
+            if withdrawn && !self.withdrawn.contains(&self.candidates[c]) {
+                let name = self.candidates[c].clone();
+                self.withdrawn.push(name);
+            }
+        }
+        for (groups, w) in reader.ballots {
+            let groups = groups
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|c| index[c])
+                        .collect::<Vec<_>>()
+                        .into_boxed_slice()
+                })
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+            self.ballots.push((groups, w));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+
+    #[test]
+    fn test_blt() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "4 2\n-4\n3 1 2 0\n# comment\n(a) 1 2=3 1 0\n0\n\
+                    \"Alice\"\n\"Bob\" \"Carol\"\nDave\n\"Title\"\n";
+        bp.add_blt(input(text)).unwrap();
+        assert_eq!(bp.candidates, ["Alice", "Bob", "Carol", "Dave"]);
+        assert_eq!(ranked(&bp), ["3: Alice > Bob", "1: Bob = Carol > Alice"]);
+        assert_eq!(bp.num_seats, Some(2));
+        assert_eq!(bp.title, Some("Title".to_string()));
+        assert_eq!(bp.withdrawn, ["Dave"]);
+    }
+
+    #[test]
+    fn test_blt_errors() {
+        let bad = |ballot: &str| {
+            let text = format!("3 1\n2 1 0\n{}\n0\n\"A\"\n\"B\"\n\"C\"\n", ballot);
+            error(parser(BallotType::Ranked).add_blt(input(&text)))
+        };
+        assert_eq!(bad("1 1 4 0"), (Some(3), "invalid candidate number: 4".to_string()));
+        assert_eq!(bad("1 1 2=1 0"), (Some(3), "candidate repeated: 1".to_string()));
+        assert_eq!(
+            bad("x 1 0"),
+            (Some(3), "cannot parse ballot weight: invalid float literal".to_string())
+        );
+        assert_eq!(bad("0.0 1 0"), (Some(3), "non-positive ballot weight".to_string()));
+        assert_eq!(bad("1 1"), (Some(3), "missing 0 at end of ballot".to_string()));
+
+        let text = "3 1\n2 1 0\n0\n\"A\"\n\"B\"\n";
+        assert_eq!(
+            error(parser(BallotType::Ranked).add_blt(input(text))),
+            (Some(6), "expected 3 candidate names, found 2".to_string())
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{group_ranks, BallotParser, BallotType, Error, ErrorKind};
+
+// CSV exports from survey tools, with a header row.  Either each column is
+// a candidate and each cell the rank given to it (lower ranks first, equal
+// ranks tied):
+//
+//   Alice,Bob,Carol
+//   1,2,
+//   2,1,1
+//
+// or each column is a rank position holding a candidate name:
+//
+//   First choice,Second choice,Third choice
+//   Alice,Bob,
+//   Carol,,
+//
+// Blank cells are unranked.  A column headed weight or count gives the
+// weight of each row.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsvLayout {
+    Ranks,
+    Choices,
+}
+
+// The line a record starts on, and its fields.
+type Record = (usize, Vec<String>);
+
+// Quoted fields may contain commas, newlines and doubled quotes.
+fn records(text: &str) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let (mut lineno, mut start) = (0, 0);
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(field.split_off(0)),
+            '\r' if !quoted => {}
+            '\n' => {
+                lineno += 1;
+                if quoted {
+                    field.push(ch);
+                } else {
+                    record.push(field.split_off(0));
+                    records.push((start, record.split_off(0)));
+                    start = lineno;
+                }
+            }
+            _ => field.push(ch),
+        }
+    }
+    if quoted {
+        let kind = ErrorKind::Unterminated {
+            what: "quoted field",
+            text: String::new(),
+        };
+        return Err(Error::at_line(start, kind));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+    Ok(records
+        .into_iter()
+        .filter(|(_, record)| record.len() > 1 || !record[0].trim().is_empty())
+        .collect())
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    fn csv_ranks(
+        &mut self,
+        record: &[String],
+        columns: &[usize],
+        candidates: &[usize],
+    ) -> Result<Box<[Box<[usize]>]>, ErrorKind> {
+        let mut ranks = Vec::new();
+        for (&i, &c) in columns.iter().zip(candidates) {
+            let cell = record[i].trim();
+            if cell.is_empty() {
+                continue;
+            }
+            match cell.parse::<usize>() {
+                Ok(rank) if rank > 0 => ranks.push((rank, c)),
+                _ => {
+                    return Err(ErrorKind::InvalidRank {
+                        candidate: Some(self.candidates[c].clone()),
+                        rank: cell.to_string(),
+                    })
+                }
+            }
+        }
+        Ok(group_ranks(ranks))
+    }
+
+    fn csv_choices(
+        &mut self,
+        record: &[String],
+        columns: &[usize],
+    ) -> Result<Box<[Box<[usize]>]>, ErrorKind> {
+        let used = &mut HashSet::new();
+        Ok(columns
+            .iter()
+            .map(|&i| record[i].trim())
+            .filter(|cell| !cell.is_empty())
+            .map(|cell| Ok(vec![self.parse_candidate(cell, used)?].into_boxed_slice()))
+            .collect::<Result<Vec<_>, ErrorKind>>()?
+            .into_boxed_slice())
+    }
+
+    pub(super) fn add_csv<R: Read>(
+        &mut self,
+        mut buf: BufReader<R>,
+        layout: CsvLayout,
+    ) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("CSV files")));
+        }
+        let mut text = String::new();
+        buf.read_to_string(&mut text)
+            .map_err(|e| Error::new(ErrorKind::Io(e)))?;
+        let mut records = records(&text)?.into_iter();
+        let (lineno, header) = records
+            .next()
+            .ok_or_else(|| Error::at_line(0, ErrorKind::Missing("header row")))?;
+
+        let weight_column = header.iter().position(|name| {
+            let name = name.trim().to_lowercase();
+            name == "weight" || name == "count"
+        });
+        let columns = (0..header.len())
+            .filter(|&i| Some(i) != weight_column)
+            .collect::<Vec<_>>();
+        let candidates = match layout {
+            CsvLayout::Ranks => {
+                let used = &mut HashSet::new();
+                columns
+                    .iter()
+                    .map(|&i| self.parse_candidate(&header[i], used))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| Error::at_line(lineno, e))?
+            }
+            CsvLayout::Choices => Vec::new(),
+        };
+
+        for (lineno, record) in records {
+            if record.len() != header.len() {
+                let kind = ErrorKind::FieldCount {
+                    what: "fields",
+                    expected: header.len(),
+                    found: record.len(),
+                };
+                return Err(Error::at_line(lineno, kind));
+            }
+            if record.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            let w = match weight_column {
+                Some(i) => {
+                    let w = W::from_str(record[i].trim()).map_err(|e| {
+                        Error::at_line(lineno, ErrorKind::InvalidWeight(e.to_string()))
+                    })?;
+                    if w <= W::zero() {
+                        return Err(Error::at_line(lineno, ErrorKind::NonPositiveWeight));
+                    }
+                    w
+                }
+                None => W::one(),
+            };
+            let groups = match layout {
+                CsvLayout::Ranks => self.csv_ranks(&record, &columns, &candidates),
+                CsvLayout::Choices => self.csv_choices(&record, &columns),
+            }
+            .map_err(|e| Error::at_line(lineno, e))?;
+            self.ballots.push((groups, w));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+
+    use super::CsvLayout;
+
+    #[test]
+    fn test_csv_ranks() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "\u{feff}Alice,\"Smith, Bob\nJr.\",Carol,Count\r\n1,2,,3\r\n2,1,1,1\r\n,,,\r\n";
+        bp.add_csv(input(text), CsvLayout::Ranks).unwrap();
+        assert_eq!(bp.candidates, ["Alice", "Smith, Bob\nJr.", "Carol"]);
+        assert_eq!(
+            ranked(&bp),
+            [
+                "3: Alice > Smith, Bob\nJr.",
+                "1: Smith, Bob\nJr. = Carol > Alice"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_choices() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "First,Second,Third\nAlice,Bob,\n\"Carol \"\"C\"\"\",,Alice\n";
+        bp.add_csv(input(text), CsvLayout::Choices).unwrap();
+        assert_eq!(bp.candidates, ["Alice", "Bob", "Carol \"C\""]);
+        assert_eq!(ranked(&bp), ["1: Alice > Bob", "1: Carol \"C\" > Alice"]);
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        let bad = |layout, record: &str| {
+            let text = format!("A,\"B\nB\",Weight\n1,2,1\n{}\n", record);
+            error(parser(BallotType::Ranked).add_csv(input(&text), layout))
+        };
+        assert_eq!(
+            bad(CsvLayout::Ranks, "1,x,1"),
+            (Some(4), "invalid rank for B\nB: x".to_string())
+        );
+        assert_eq!(
+            bad(CsvLayout::Ranks, "1,2,x"),
+            (
+                Some(4),
+                "cannot parse ballot weight: invalid float literal".to_string()
+            )
+        );
+        assert_eq!(
+            bad(CsvLayout::Ranks, "1,2,-1"),
+            (Some(4), "non-positive ballot weight".to_string())
+        );
+        assert_eq!(
+            bad(CsvLayout::Ranks, "1,2"),
+            (Some(4), "expected 3 fields, found 2".to_string())
+        );
+        assert_eq!(
+            bad(CsvLayout::Ranks, "\"1,2,1"),
+            (Some(4), "unterminated quoted field".to_string())
+        );
+        assert_eq!(
+            bad(CsvLayout::Choices, "C, C ,1"),
+            (Some(4), "candidate repeated: C".to_string())
+        );
+
+        let text = "A,A\n1,2\n";
+        assert_eq!(
+            error(parser(BallotType::Ranked).add_csv(input(text), CsvLayout::Ranks)),
+            (Some(1), "candidate repeated: A".to_string())
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{group_ranks, BallotParser, BallotType, Error, ErrorKind};
+
+// Debian vote files, one ballot each, possibly inside a signed email.  Each
+// option is ranked in brackets; blank options rank last:
+//
+//   [ 1 ] Choice 1: Foo
+//   [ 2 ] Choice 2: Bar
+//   [   ] Choice 3: None of the above
+//
+// Debian tally sheets name the options, then give one vote per line, each
+// character ranking the option in that position (- for unranked):
+//
+//      Option 1----->: Choice 1: Foo
+//    /  Option 2---->: Choice 2: Bar
+//    |/  Option 3--->: Choice 3: None of the above
+//    ||/
+//   V: 12-        5d2a6b...
+
+// [ RANK ] NAME, with the rank blank or -.
+fn parse_option(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix('[')?;
+    let end = rest.find(']')?;
+    let rank = rest[..end].trim();
+    if !rank.chars().all(|ch| ch.is_ascii_digit() || ch == '-') {
+        return None;
+    }
+    Some((rank, rest[end + 1..].trim()))
+}
+
+// Option N...: NAME
+fn parse_option_name(line: &str) -> Option<(usize, &str)> {
+    let start = line.find("Option ")? + "Option ".len();
+    let rest = &line[start..];
+    let end = rest
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let number = rest[..end].parse().ok()?;
+    let colon = rest.find(':')?;
+    Some((number, rest[colon + 1..].trim()))
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    pub(super) fn add_debian_vote<R: Read>(
+        &mut self,
+        buf: BufReader<R>,
+    ) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("Debian vote files")));
+        }
+        let mut ranks = Vec::new();
+        let mut used = Vec::new();
+        let mut num_lines = 0;
+        for (lineno, line) in buf.lines().enumerate() {
+            let line = line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?;
+            num_lines = lineno + 1;
+            let (rank, name) = match parse_option(&line) {
+                Some(option) => option,
+                None => continue,
+            };
+            if name.is_empty() {
+                return Err(Error::at_line(lineno, ErrorKind::EmptyCandidateName));
+            }
+            let c = self.candidate(name);
+            if used.contains(&c) {
+                let e = ErrorKind::RepeatedCandidate(name.to_string());
+                return Err(Error::at_line(lineno, e));
+            }
+            used.push(c);
+            if !rank.is_empty() && rank != "-" {
+                match rank.parse::<usize>() {
+                    Ok(rank) if rank > 0 => ranks.push((rank, c)),
+                    _ => {
+                        let kind = ErrorKind::InvalidRank {
+                            candidate: Some(name.to_string()),
+                            rank: rank.to_string(),
+                        };
+                        return Err(Error::at_line(lineno, kind));
+                    }
+                }
+            }
+        }
+        if used.is_empty() {
+            return Err(Error::at_line(
+                num_lines,
+                ErrorKind::Missing("ranked options"),
+            ));
+        }
+        self.ballots.push((group_ranks(ranks), W::one()));
+        Ok(())
+    }
+
+    pub(super) fn add_debian_tally<R: Read>(
+        &mut self,
+        buf: BufReader<R>,
+    ) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("Debian tally sheets")));
+        }
+        let mut names: Vec<Option<String>> = Vec::new();
+        let mut options: Option<Vec<usize>> = None;
+        for (lineno, line) in buf.lines().enumerate() {
+            let line = line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?;
+            let vote = match line.trim_start().strip_prefix("V:") {
+                Some(vote) => vote.split_whitespace().next().unwrap_or(""),
+                None => {
+                    if let Some((number, name)) = parse_option_name(&line) {
+                        if options.is_some() || number == 0 {
+                            let kind = ErrorKind::UnexpectedOption(line.trim().to_string());
+                            return Err(Error::at_line(lineno, kind));
+                        }
+                        if names.len() < number {
+                            names.resize(number, None);
+                        }
+                        names[number - 1] = Some(name.to_string());
+                    }
+                    continue;
+                }
+            };
+
+            if options.is_none() {
+                let mut index = Vec::with_capacity(names.len());
+                for (i, name) in names.iter().enumerate() {
+                    match *name {
+                        Some(ref name) if !name.is_empty() => index.push(self.candidate(name)),
+                        _ => {
+                            let kind = ErrorKind::UnnamedOption(i + 1);
+                            return Err(Error::at_line(lineno, kind));
+                        }
+                    }
+                }
+                options = Some(index);
+            }
+            let index = options.as_ref().unwrap();
+            if vote.chars().count() != index.len() {
+                let kind = ErrorKind::FieldCount {
+                    what: "ranks",
+                    expected: index.len(),
+                    found: vote.chars().count(),
+                };
+                return Err(Error::at_line(lineno, kind));
+            }
+            let mut ranks = Vec::new();
+            for (ch, &c) in vote.chars().zip(index) {
+                if ch == '-' {
+                    continue;
+                }
+                match ch.to_digit(36) {
+                    Some(rank) if rank > 0 => ranks.push((rank as usize, c)),
+                    _ => {
+                        let kind = ErrorKind::InvalidRank {
+                            candidate: None,
+                            rank: ch.to_string(),
+                        };
+                        return Err(Error::at_line(lineno, kind));
+                    }
+                }
+            }
+            self.ballots.push((group_ranks(ranks), W::one()));
+        }
+        if options.is_none() {
+            return Err(Error::new(ErrorKind::Missing("votes")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+
+    #[test]
+    fn test_debian_vote() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "-----BEGIN PGP SIGNED MESSAGE-----\n\n[ 2 ] Foo\n[ 1 ] Bar\n[ - ] Baz\n\
+                    [   ] None of the above\n[ 2 ] Qux\n";
+        bp.add_debian_vote(input(text)).unwrap();
+        assert_eq!(
+            bp.candidates,
+            ["Foo", "Bar", "Baz", "None of the above", "Qux"]
+        );
+        assert_eq!(ranked(&bp), ["1: Bar > Foo = Qux"]);
+    }
+
+    #[test]
+    fn test_debian_tally() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "     Option 1----->: Foo\n   /  Option 2---->: Bar\n   |/  Option 3--->: Baz\n\
+                    ||/\nV: 12-        5d2a6b\nV: 1-1        0c9e1f\n";
+        bp.add_debian_tally(input(text)).unwrap();
+        assert_eq!(bp.candidates, ["Foo", "Bar", "Baz"]);
+        assert_eq!(ranked(&bp), ["1: Foo > Bar", "1: Foo = Baz"]);
+    }
+
+    #[test]
+    fn test_debian_errors() {
+        let vote = |text: &str| error(parser(BallotType::Ranked).add_debian_vote(input(text)));
+        assert_eq!(
+            vote("[ 1 ] Foo\n[ 2 ] Foo\n"),
+            (Some(2), "candidate repeated: Foo".to_string())
+        );
+        assert_eq!(
+            vote("[ 0 ] Foo\n"),
+            (Some(1), "invalid rank for Foo: 0".to_string())
+        );
+        assert_eq!(vote("[ 1 ]\n"), (Some(1), "empty candidate name".to_string()));
+        assert_eq!(vote("Hello\n"), (Some(2), "missing ranked options".to_string()));
+
+        let tally = |text: &str| error(parser(BallotType::Ranked).add_debian_tally(input(text)));
+        let options = "Option 1--->: Foo\nOption 2-->: Bar\n";
+        assert_eq!(
+            tally(&format!("{}V: 1-- x\n", options)),
+            (Some(3), "expected 2 ranks, found 3".to_string())
+        );
+        assert_eq!(
+            tally(&format!("{}V: 1* x\n", options)),
+            (Some(3), "invalid rank: *".to_string())
+        );
+        assert_eq!(
+            tally("Option 2-->: Bar\nV: 11 x\n"),
+            (Some(2), "missing name for option 1".to_string())
+        );
+        assert_eq!(tally(options), (None, "missing votes".to_string()));
+    }
+}
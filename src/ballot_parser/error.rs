@@ -0,0 +1,333 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+// Where in a ballot file an error is.  Lines and columns count from 1;
+// JSON files give the ballot instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub ballot: Option<usize>,
+    // The line, and the width in characters of the part at fault, to point
+    // it out.
+    pub source: Option<String>,
+    pub width: usize,
+}
+
+impl Span {
+    // Nothing to report: the error is not in a known file.
+    pub fn is_empty(&self) -> bool {
+        self.file.is_empty() && self.line.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    EmptyCandidateName,
+    UnknownCandidate {
+        name: String,
+        suggestion: Option<String>,
+    },
+    RepeatedCandidate(String),
+    InvalidWeight(String),
+    NonPositiveWeight,
+    InvalidScore {
+        candidate: String,
+        reason: String,
+    },
+    NegativeScore(String),
+    SeatsMismatch {
+        seats: usize,
+        earlier: usize,
+    },
+    MethodMismatch {
+        method: String,
+        earlier: String,
+    },
+    // The kind of file, for formats without approval or score ballots.
+    RankedBallotsOnly(&'static str),
+    // Parsing stopped after this many invalid lines.
+    TooManyErrors(usize),
+    UnexpectedQuote(String),
+    InvalidEscape(String),
+    // A quoted name or string, or a bracket, with no end; and the text from
+    // where it starts, if any.
+    Unterminated {
+        what: &'static str,
+        text: String,
+    },
+    UnexpectedText {
+        after: &'static str,
+        text: String,
+    },
+    // A separator the format needs before this text.
+    Expected {
+        what: &'static str,
+        before: String,
+    },
+    // A part of the file the format needs, such as a header.
+    Missing(&'static str),
+    MissingScore(String),
+    FieldCount {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber {
+        what: &'static str,
+        reason: String,
+    },
+    // A BLT candidate or PrefLib alternative number out of range.
+    InvalidCandidateNumber(String),
+    InvalidRank {
+        candidate: Option<String>,
+        rank: String,
+    },
+    // Debian tally sheets: an option after the votes, or numbered 0, and an
+    // option voted on without a name.
+    UnexpectedOption(String),
+    UnnamedOption(usize),
+    // JSON files.
+    InvalidJson(String),
+    UnknownMember(String),
+    WrongType {
+        what: &'static str,
+        expected: &'static str,
+    },
+}
+
+#[derive(Debug)]
+pub struct Error {
+    // Boxed, to keep results small.
+    pub span: Box<Span>,
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    // Somewhere in a file not known yet.
+    pub(super) fn new(kind: ErrorKind) -> Error {
+        Error {
+            span: Box::default(),
+            kind,
+        }
+    }
+
+    // At a line counting from 0, as the parsers do.
+    pub(super) fn at_line(lineno: usize, kind: ErrorKind) -> Error {
+        let mut error = Error::new(kind);
+        error.span.line = Some(lineno + 1);
+        error
+    }
+
+    // FILE:LINE:COLUMN: SEVERITY: MESSAGE, then the line with carets under
+    // the part at fault, if known.
+    pub fn report(&self, severity: &str) -> String {
+        let mut report = if self.span.is_empty() {
+            format!("{}: ", severity)
+        } else {
+            format!("{}: {}: ", self.span, severity)
+        };
+        if let Some(ballot) = self.span.ballot {
+            report.push_str(&format!("ballot {}: ", ballot));
+        }
+        report.push_str(&self.kind.to_string());
+        if let (Some(source), Some(column)) = (&self.span.source, self.span.column) {
+            let indent = source
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let carets = "^".repeat(self.span.width.max(1));
+            report.push_str(&format!("\n  {}\n  {}{}", source, indent, carets));
+        }
+        report
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::Io(ref e) => write!(f, "{}", e),
+            ErrorKind::EmptyCandidateName => write!(f, "empty candidate name"),
+            ErrorKind::UnknownCandidate {
+                ref name,
+                suggestion: Some(ref suggestion),
+            } => write!(
+                f,
+                "unknown candidate: {} (did you mean {}?)",
+                name, suggestion
+            ),
+            ErrorKind::UnknownCandidate { ref name, .. } => {
+                write!(f, "unknown candidate: {}", name)
+            }
+            ErrorKind::RepeatedCandidate(ref name) => write!(f, "candidate repeated: {}", name),
+            ErrorKind::InvalidWeight(ref reason) => {
+                write!(f, "cannot parse ballot weight: {}", reason)
+            }
+            ErrorKind::NonPositiveWeight => write!(f, "non-positive ballot weight"),
+            ErrorKind::InvalidScore {
+                ref candidate,
+                ref reason,
+            } => write!(f, "cannot parse score for {}: {}", candidate, reason),
+            ErrorKind::NegativeScore(ref name) => {
+                write!(f, "negative score for candidate: {}", name)
+            }
+            ErrorKind::SeatsMismatch { seats, earlier } => write!(
+                f,
+                "number of seats {} differs from earlier files ({})",
+                seats, earlier
+            ),
+            ErrorKind::MethodMismatch {
+                ref method,
+                ref earlier,
+            } => write!(
+                f,
+                "method {} differs from earlier files ({})",
+                method, earlier
+            ),
+            ErrorKind::RankedBallotsOnly(files) => {
+                write!(f, "{} only contain ranked ballots", files)
+            }
+            ErrorKind::TooManyErrors(limit) => write!(f, "stopping after {} invalid lines", limit),
+            ErrorKind::UnexpectedQuote(ref text) => write!(f, "unexpected quote in name: {}", text),
+            ErrorKind::InvalidEscape(ref text) => write!(f, "invalid escape in name: {}", text),
+            ErrorKind::Unterminated { what, ref text } if text.is_empty() => {
+                write!(f, "unterminated {}", what)
+            }
+            ErrorKind::Unterminated { what, ref text } => {
+                write!(f, "unterminated {}: {}", what, text)
+            }
+            ErrorKind::UnexpectedText { after, ref text } => {
+                write!(f, "unexpected text after {}: {}", after, text)
+            }
+            ErrorKind::Expected { what, ref before } => {
+                write!(f, "expected {} before {}", what, before)
+            }
+            ErrorKind::Missing(what) => write!(f, "missing {}", what),
+            ErrorKind::MissingScore(ref name) => write!(f, "missing score for candidate: {}", name),
+            ErrorKind::FieldCount {
+                what,
+                expected,
+                found,
+            } => write!(f, "expected {} {}, found {}", expected, what, found),
+            ErrorKind::InvalidNumber { what, ref reason } => {
+                write!(f, "cannot parse number of {}: {}", what, reason)
+            }
+            ErrorKind::InvalidCandidateNumber(ref token) => {
+                write!(f, "invalid candidate number: {}", token)
+            }
+            ErrorKind::InvalidRank {
+                candidate: Some(ref candidate),
+                ref rank,
+            } => write!(f, "invalid rank for {}: {}", candidate, rank),
+            ErrorKind::InvalidRank { ref rank, .. } => write!(f, "invalid rank: {}", rank),
+            ErrorKind::UnexpectedOption(ref line) => write!(f, "unexpected option: {}", line),
+            ErrorKind::UnnamedOption(number) => write!(f, "missing name for option {}", number),
+            ErrorKind::InvalidJson(ref message) => write!(f, "{}", message),
+            ErrorKind::UnknownMember(ref key) => write!(f, "unknown member: {}", key),
+            ErrorKind::WrongType { what, expected } => write!(f, "{} must be {}", what, expected),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.span.is_empty() {
+            write!(f, "{}: ", self.span)?;
+        }
+        if let Some(ballot) = self.span.ballot {
+            write!(f, "ballot {}: ", ballot)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            ErrorKind::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorKind, Span};
+
+    #[test]
+    fn test_span() {
+        let mut span = Span {
+            file: "votes.txt".to_string(),
+            ..Span::default()
+        };
+        assert_eq!(span.to_string(), "votes.txt");
+        span.column = Some(3);
+        assert_eq!(span.to_string(), "votes.txt");
+        span.line = Some(2);
+        assert_eq!(span.to_string(), "votes.txt:2:3");
+        span.column = None;
+        assert_eq!(span.to_string(), "votes.txt:2");
+    }
+
+    #[test]
+    fn test_report() {
+        let mut e = Error::at_line(1, ErrorKind::RepeatedCandidate("Bob".to_string()));
+        e.span.file = "votes.txt".to_string();
+        assert_eq!(
+            e.report("error"),
+            "votes.txt:2: error: candidate repeated: Bob"
+        );
+
+        e.span.column = Some(10);
+        e.span.width = 3;
+        e.span.source = Some("\tBob > A > Bob".to_string());
+        assert_eq!(
+            e.report("warning"),
+            "votes.txt:2:10: warning: candidate repeated: Bob\n  \tBob > A > Bob\n  \t        ^^^"
+        );
+
+        // Bad columns still point somewhere.
+        e.span.column = Some(0);
+        e.span.width = 0;
+        assert_eq!(
+            e.report("error"),
+            "votes.txt:2:0: error: candidate repeated: Bob\n  \tBob > A > Bob\n  ^"
+        );
+
+        let mut e = Error::new(ErrorKind::NonPositiveWeight);
+        e.span.file = "votes.json".to_string();
+        e.span.ballot = Some(4);
+        assert_eq!(
+            e.report("error"),
+            "votes.json: error: ballot 4: non-positive ballot weight"
+        );
+        assert_eq!(
+            e.to_string(),
+            "votes.json: ballot 4: non-positive ballot weight"
+        );
+
+        e.span.file.clear();
+        assert_eq!(
+            e.report("error"),
+            "error: ballot 4: non-positive ballot weight"
+        );
+        assert_eq!(e.to_string(), "ballot 4: non-positive ballot weight");
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{BallotParser, BallotType, Error, ErrorKind};
+use json::Json;
+
+// A JSON election description:
+//
+//   {
+//     "title": "Election title",
+//     "candidates": ["Alice", "Bob", "Carol"],
+//     "seats": 1,
+//     "method": "schulze",
+//     "ballots": [
+//       {"ranking": ["Alice", ["Bob", "Carol"]], "weight": 3},
+//       ["Carol", "Alice"]
+//     ]
+//   }
+//
+// Each ranking lists candidates or arrays of tied candidates; a ballot
+// without a weight counts once.  When candidates are listed, other names
+// are errors, as after #candidates: in the line format.  All members but
+// ballots are optional.
+
+fn string<'a>(value: &'a Json, what: &'static str) -> Result<&'a str, ErrorKind> {
+    match *value {
+        Json::String(ref s) => Ok(s),
+        _ => Err(ErrorKind::WrongType {
+            what,
+            expected: "a string",
+        }),
+    }
+}
+
+fn array<'a>(value: &'a Json, what: &'static str) -> Result<&'a [Json], ErrorKind> {
+    match *value {
+        Json::Array(ref elements) => Ok(elements),
+        _ => Err(ErrorKind::WrongType {
+            what,
+            expected: "an array",
+        }),
+    }
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    fn json_candidate(
+        &mut self,
+        value: &Json,
+        used: &mut HashSet<usize>,
+    ) -> Result<usize, ErrorKind> {
+        let name = string(value, "candidate")?;
+        self.parse_candidate(name, used)
+    }
+
+    fn json_ballot(&mut self, ballot: &Json) -> Result<(), ErrorKind> {
+        let (ranking, w) = match *ballot {
+            Json::Object(_) => {
+                let ranking = ballot.get("ranking").ok_or(ErrorKind::Missing("ranking"))?;
+                let w = match ballot.get("weight") {
+                    Some(Json::Number(w)) | Some(Json::String(w)) => W::from_str(w)
+                        .map_err(|e| ErrorKind::InvalidWeight(e.to_string()))?,
+                    Some(_) => {
+                        return Err(ErrorKind::WrongType {
+                            what: "weight",
+                            expected: "a number or string",
+                        })
+                    }
+                    None => W::one(),
+                };
+                (ranking, w)
+            }
+            _ => (ballot, W::one()),
+        };
+        if w <= W::zero() {
+            return Err(ErrorKind::NonPositiveWeight);
+        }
+
+        let used = &mut HashSet::new();
+        let mut groups = Vec::new();
+        for group in array(ranking, "ranking")? {
+            let group = match *group {
+                Json::Array(ref names) => names
+                    .iter()
+                    .map(|name| self.json_candidate(name, used))
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => vec![self.json_candidate(group, used)?],
+            };
+            if !group.is_empty() {
+                groups.push(group.into_boxed_slice());
+            }
+        }
+        self.ballots.push((groups.into_boxed_slice(), w));
+        Ok(())
+    }
+
+    fn json_election(&mut self, election: &Json) -> Result<(), Error> {
+        if let Json::Object(ref members) = *election {
+            for (key, _) in members {
+                if !["title", "candidates", "seats", "method", "ballots"].contains(&&key[..]) {
+                    return Err(Error::new(ErrorKind::UnknownMember(key.to_string())));
+                }
+            }
+        } else {
+            return Err(Error::new(ErrorKind::WrongType {
+                what: "election",
+                expected: "an object",
+            }));
+        }
+        self.json_members(election).map_err(Error::new)?;
+
+        let ballots = election
+            .get("ballots")
+            .ok_or_else(|| Error::new(ErrorKind::Missing("ballots")))?;
+        let ballots = array(ballots, "ballots").map_err(Error::new)?;
+        for (i, ballot) in ballots.iter().enumerate() {
+            self.json_ballot(ballot).map_err(|e| {
+                let mut error = Error::new(e);
+                error.span.ballot = Some(i + 1);
+                error
+            })?;
+        }
+        Ok(())
+    }
+
+    // Everything but the ballots.
+    fn json_members(&mut self, election: &Json) -> Result<(), ErrorKind> {
+        if let Some(title) = election.get("title") {
+            let title = string(title, "title")?;
+            if self.title.is_none() {
+                self.title = Some(title.to_string());
+            }
+        }
+        if let Some(method) = election.get("method") {
+            let method = string(method, "method")?;
+            match self.method {
+                Some(ref method1) if method1 != method => {
+                    return Err(ErrorKind::MethodMismatch {
+                        method: method.to_string(),
+                        earlier: method1.clone(),
+                    })
+                }
+                _ => self.method = Some(method.to_string()),
+            }
+        }
+        match election.get("seats") {
+            Some(Json::Number(seats)) => {
+                let seats = seats
+                    .parse::<usize>()
+                    .map_err(|e| ErrorKind::InvalidNumber {
+                        what: "seats",
+                        reason: e.to_string(),
+                    })?;
+                self.set_num_seats(seats)?;
+            }
+            Some(_) => {
+                return Err(ErrorKind::WrongType {
+                    what: "seats",
+                    expected: "a number",
+                })
+            }
+            None => {}
+        }
+
+        if let Some(candidates) = election.get("candidates") {
+            let used = &mut HashSet::new();
+            for name in array(candidates, "candidates")? {
+                let name = string(name, "candidate")?;
+                if name.trim().is_empty() {
+                    return Err(ErrorKind::EmptyCandidateName);
+                }
+                let c = self.candidate(name.trim());
+                if !used.insert(c) {
+                    return Err(ErrorKind::RepeatedCandidate(name.to_string()));
+                }
+            }
+            self.declared = true;
+        }
+        Ok(())
+    }
+
+    pub(super) fn add_json<R: Read>(
+        &mut self,
+        mut buf: BufReader<R>,
+    ) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("JSON files")));
+        }
+        let mut text = String::new();
+        buf.read_to_string(&mut text)
+            .map_err(|e| Error::new(ErrorKind::Io(e)))?;
+        let election = Json::parse(&text)
+            .map_err(|(lineno, e)| Error::at_line(lineno, ErrorKind::InvalidJson(e)))?;
+        self.json_election(&election)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+
+    #[test]
+    fn test_json() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = r#"{
+            "title": "Title",
+            "candidates": ["Alice", "Bob", "Carol"],
+            "seats": 2,
+            "method": "meek-stv",
+            "ballots": [
+                {"ranking": ["Alice", ["Bob", "Carol"]], "weight": 3},
+                {"ranking": [[], "Carol"], "weight": "1.5"},
+                ["Bob"]
+            ]
+        }"#;
+        bp.add_json(input(text)).unwrap();
+        assert_eq!(bp.candidates, ["Alice", "Bob", "Carol"]);
+        assert_eq!(
+            ranked(&bp),
+            ["3: Alice > Bob = Carol", "1.5: Carol", "1: Bob"]
+        );
+        assert_eq!(bp.title, Some("Title".to_string()));
+        assert_eq!(bp.num_seats, Some(2));
+        assert_eq!(bp.method, Some("meek-stv".to_string()));
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let bad = |ballot: &str| {
+            let text = format!(
+                "{{\"candidates\": [\"Alice\", \"Bob\"], \"ballots\": [[], {}]}}",
+                ballot
+            );
+            let e = parser(BallotType::Ranked)
+                .add_json(input(&text))
+                .unwrap_err();
+            (e.span.ballot, e.to_string())
+        };
+        assert_eq!(
+            bad("[\"Alcie\"]"),
+            (Some(2), "ballot 2: unknown candidate: Alcie (did you mean Alice?)".to_string())
+        );
+        assert_eq!(
+            bad("[\"Bob\", [\"Bob\"]]"),
+            (Some(2), "ballot 2: candidate repeated: Bob".to_string())
+        );
+        assert_eq!(
+            bad("{\"ranking\": [], \"weight\": \"x\"}"),
+            (
+                Some(2),
+                "ballot 2: cannot parse ballot weight: invalid float literal".to_string()
+            )
+        );
+        assert_eq!(
+            bad("{\"ranking\": [], \"weight\": 0}"),
+            (Some(2), "ballot 2: non-positive ballot weight".to_string())
+        );
+        assert_eq!(
+            bad("[1]"),
+            (Some(2), "ballot 2: candidate must be a string".to_string())
+        );
+
+        let e = parser(BallotType::Ranked).add_json(input("{\"ballots\": [],\n\"seat\": 1}"));
+        assert_eq!(error(e), (None, "unknown member: seat".to_string()));
+        let e = parser(BallotType::Ranked).add_json(input("{\"ballots\": [\n}"));
+        assert_eq!(error(e), (Some(2), "unexpected character: }".to_string()));
+    }
+}
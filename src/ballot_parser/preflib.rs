@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+use vote::traits::Weight;
+
+use super::{BallotParser, BallotType, Error, ErrorKind};
+
+// The PrefLib formats for strict (.soc, .soi) and tied (.toc, .toi) orders:
+//
+//   # TITLE: Election title
+//   # NUMBER ALTERNATIVES: 3
+//   # ALTERNATIVE NAME 1: Alice
+//   # ALTERNATIVE NAME 2: Bob
+//   # ALTERNATIVE NAME 3: Carol
+//   12: 1,2,3
+//   3: 2,{1,3}
+//
+// Other metadata is ignored.  Alternatives without a name go by their
+// numbers, and alternatives missing from an incomplete order rank last, as
+// usual.
+
+struct PrefLibReader<W> {
+    num_alternatives: Option<usize>,
+    names: Vec<Option<String>>,
+    title: Option<String>,
+    ballots: Vec<(Vec<Vec<usize>>, W)>,
+}
+
+impl<W: FromStr + Weight> PrefLibReader<W>
+where
+    W::Err: Display,
+{
+    fn alternative(&self, token: &str) -> Result<usize, ErrorKind> {
+        let num_alternatives = self.num_alternatives.unwrap_or(0);
+        match token.trim().parse::<usize>() {
+            Ok(c) if c >= 1 && c <= num_alternatives => Ok(c - 1),
+            _ => Err(ErrorKind::InvalidCandidateNumber(token.trim().to_string())),
+        }
+    }
+
+    fn metadata(&mut self, line: &str) -> Result<(), ErrorKind> {
+        let i = match line.find(':') {
+            Some(i) => i,
+            None => return Ok(()),
+        };
+        let (key, value) = (line[..i].trim(), line[i + 1..].trim());
+        if key == "TITLE" {
+            self.title = Some(value.to_string());
+        } else if key == "NUMBER ALTERNATIVES" {
+            let n = value
+                .parse::<usize>()
+                .map_err(|e| ErrorKind::InvalidNumber {
+                    what: "alternatives",
+                    reason: e.to_string(),
+                })?;
+            self.num_alternatives = Some(n);
+            self.names.resize(n, None);
+        } else if let Some(number) = key.strip_prefix("ALTERNATIVE NAME ") {
+            let c = self.alternative(number)?;
+            self.names[c] = Some(value.to_string());
+        }
+        Ok(())
+    }
+
+    fn ballot(&mut self, line: &str) -> Result<(), ErrorKind> {
+        if self.num_alternatives.is_none() {
+            return Err(ErrorKind::Missing(
+                "NUMBER ALTERNATIVES before the first order",
+            ));
+        }
+        let i = line
+            .find(':')
+            .ok_or(ErrorKind::Missing("count before order"))?;
+        let w = W::from_str(line[..i].trim())
+            .map_err(|e| ErrorKind::InvalidWeight(e.to_string()))?;
+        if w <= W::zero() {
+            return Err(ErrorKind::NonPositiveWeight);
+        }
+
+        let mut used = vec![false; self.num_alternatives.unwrap()];
+        let mut groups = Vec::new();
+        let mut rest = line[i + 1..].trim();
+        while !rest.is_empty() {
+            let (group, next) = if let Some(braced) = rest.strip_prefix('{') {
+                let end = braced.find('}').ok_or_else(|| ErrorKind::Unterminated {
+                    what: "{",
+                    text: rest.to_string(),
+                })?;
+                (&braced[..end], braced[end + 1..].trim_start())
+            } else {
+                match rest.find(',') {
+                    Some(end) => (&rest[..end], &rest[end..]),
+                    None => (rest, ""),
+                }
+            };
+            let mut alternatives = Vec::new();
+            for token in group.split(',').filter(|token| !token.trim().is_empty()) {
+                let c = self.alternative(token)?;
+                if used[c] {
+                    return Err(ErrorKind::RepeatedCandidate(token.trim().to_string()));
+                }
+                used[c] = true;
+                alternatives.push(c);
+            }
+            if !alternatives.is_empty() {
+                groups.push(alternatives);
+            }
+            rest = if let Some(next) = next.strip_prefix(',') {
+                next.trim_start()
+            } else if next.is_empty() {
+                next
+            } else {
+                return Err(ErrorKind::Expected {
+                    what: ",",
+                    before: next.to_string(),
+                });
+            };
+        }
+        self.ballots.push((groups, w));
+        Ok(())
+    }
+}
+
+impl<W: FromStr + Weight> BallotParser<W>
+where
+    W::Err: Display,
+{
+    pub(super) fn add_preflib<R: Read>(
+        &mut self,
+        buf: BufReader<R>,
+    ) -> Result<(), Error> {
+        if self.ballot_type != BallotType::Ranked {
+            return Err(Error::new(ErrorKind::RankedBallotsOnly("PrefLib files")));
+        }
+        let mut reader = PrefLibReader {
+            num_alternatives: None,
+            names: Vec::new(),
+            title: None,
+            ballots: Vec::new(),
+        };
+        for (lineno, line) in buf.lines().enumerate() {
+            let line = line.map_err(|e| Error::at_line(lineno, ErrorKind::Io(e)))?;
+            let line = line.trim();
+            if let Some(metadata) = line.strip_prefix('#') {
+                reader
+                    .metadata(metadata)
+                    .map_err(|e| Error::at_line(lineno, e))?;
+            } else if !line.is_empty() {
+                reader.ballot(line).map_err(|e| Error::at_line(lineno, e))?;
+            }
+        }
+
+        if self.title.is_none() {
+            self.title = reader.title;
+        }
+        let names = reader
+            .names
+            .into_iter()
+            .enumerate()
+            .map(|(c, name)| name.unwrap_or_else(|| (c + 1).to_string()))
+            .collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        if let Some(name) = names.iter().find(|&name| !seen.insert(name)) {
+            return Err(Error::new(ErrorKind::RepeatedCandidate(name.clone())));
+        }
+        let index = names
+            .iter()
+            .map(|name| self.candidate(name))
+            .collect::<Vec<_>>();
+        for (groups, w) in reader.ballots {
+            let groups = groups
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|c| index[c])
+                        .collect::<Vec<_>>()
+                        .into_boxed_slice()
+                })
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+            self.ballots.push((groups, w));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ballot_parser::tests::{error, input, parser, ranked};
+    use ballot_parser::BallotType;
+
+    #[test]
+    fn test_preflib() {
+        let mut bp = parser(BallotType::Ranked);
+        let text = "# TITLE: Title\n# NUMBER ALTERNATIVES: 3\n# ALTERNATIVE NAME 1: Alice\n\
+                    # ALTERNATIVE NAME 3: Carol\n12: 1,2,3\n3: 2,{1,3}\n1: {3}\n";
+        bp.add_preflib(input(text)).unwrap();
+        // Unnamed alternatives go by their numbers.
+        assert_eq!(bp.candidates, ["Alice", "2", "Carol"]);
+        assert_eq!(
+            ranked(&bp),
+            ["12: Alice > 2 > Carol", "3: 2 > Alice = Carol", "1: Carol"]
+        );
+        assert_eq!(bp.title, Some("Title".to_string()));
+    }
+
+    #[test]
+    fn test_preflib_errors() {
+        let bad = |ballot: &str| {
+            let text = format!("# NUMBER ALTERNATIVES: 2\n1: 1,2\n{}\n", ballot);
+            error(parser(BallotType::Ranked).add_preflib(input(&text)))
+        };
+        assert_eq!(
+            bad("1: 1,3"),
+            (Some(3), "invalid candidate number: 3".to_string())
+        );
+        assert_eq!(
+            bad("1: {1,2},1"),
+            (Some(3), "candidate repeated: 1".to_string())
+        );
+        assert_eq!(
+            bad("x: 1"),
+            (
+                Some(3),
+                "cannot parse ballot weight: invalid float literal".to_string()
+            )
+        );
+        assert_eq!(
+            bad("1: {1,2"),
+            (Some(3), "unterminated {: {1,2".to_string())
+        );
+        assert_eq!(
+            bad("1 2"),
+            (Some(3), "missing count before order".to_string())
+        );
+
+        let text = "1: 1\n";
+        assert_eq!(
+            error(parser(BallotType::Ranked).add_preflib(input(text))),
+            (
+                Some(1),
+                "missing NUMBER ALTERNATIVES before the first order".to_string()
+            )
+        );
+
+        // The second alternative goes by its number, which the first has.
+        let text = "# NUMBER ALTERNATIVES: 2\n# ALTERNATIVE NAME 1: 2\n1: 1,2\n";
+        assert_eq!(
+            error(parser(BallotType::Ranked).add_preflib(input(text))),
+            (None, "candidate repeated: 2".to_string())
+        );
+    }
+}
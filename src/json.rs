@@ -0,0 +1,358 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::result::Result;
+use std::str::CharIndices;
+
+// Just enough JSON for election descriptions and results.  Numbers keep
+// their text, so that weights parse exactly.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Some(&(i, _)) => i,
+            None => self.text.len(),
+        }
+    }
+
+    fn error<T>(&mut self, message: &str) -> Result<T, (usize, String)> {
+        let i = self.position();
+        let lineno = self.text[..i].matches('\n').count();
+        Err((lineno, message.to_string()))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&(_, ch)) = self.chars.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), (usize, String)> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some(&(_, ch)) if ch == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            _ => self.error(&format!("expected {}", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, (usize, String)> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'u')) => {
+                        let mut code = self.hex4()?;
+                        if (0xd800..0xdc00).contains(&code) {
+                            let low = match (self.chars.next(), self.chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => self.hex4()?,
+                                _ => return self.error("invalid \\u escape"),
+                            };
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return self.error("invalid \\u escape");
+                            }
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        }
+                        match ::std::char::from_u32(code) {
+                            Some(ch) => s.push(ch),
+                            None => return self.error("invalid \\u escape"),
+                        }
+                    }
+                    Some((_, ch)) if ch == '"' || ch == '\\' || ch == '/' => s.push(ch),
+                    _ => return self.error("invalid escape"),
+                },
+                Some((_, ch)) if ch >= ' ' => s.push(ch),
+                _ => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, (usize, String)> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.chars.next().and_then(|(_, ch)| ch.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return self.error("invalid \\u escape"),
+            }
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<String, (usize, String)> {
+        let start = self.position();
+        while let Some(&(_, ch)) = self.chars.peek() {
+            if !(ch.is_ascii_digit() || "+-.eE".contains(ch)) {
+                break;
+            }
+            self.chars.next();
+        }
+        let number = &self.text[start..self.position()];
+        if !is_number(number) {
+            return self.error(&format!("invalid number: {}", number));
+        }
+        Ok(number.to_string())
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, (usize, String)> {
+        for expected in keyword.chars() {
+            match self.chars.next() {
+                Some((_, ch)) if ch == expected => {}
+                _ => return self.error(&format!("expected {}", keyword)),
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, (usize, String)> {
+        self.skip_whitespace();
+        match self.chars.peek().map(|&(_, ch)| ch) {
+            Some('{') => {
+                self.chars.next();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek().map(|&(_, ch)| ch) == Some('}') {
+                    self.chars.next();
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    members.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some((_, ',')) => {}
+                        Some((_, '}')) => return Ok(Json::Object(members)),
+                        _ => return self.error("expected , or }"),
+                    }
+                }
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut elements = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek().map(|&(_, ch)| ch) == Some(']') {
+                    self.chars.next();
+                    return Ok(Json::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some((_, ',')) => {}
+                        Some((_, ']')) => return Ok(Json::Array(elements)),
+                        _ => return self.error("expected , or ]"),
+                    }
+                }
+            }
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => Ok(Json::Number(self.number()?)),
+            Some(ch) => self.error(&format!("unexpected character: {}", ch)),
+            None => self.error("unexpected end of input"),
+        }
+    }
+}
+
+// -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+pub fn is_number(s: &str) -> bool {
+    fn digits(s: &str) -> (&str, &str) {
+        let n = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+        (&s[..n], &s[n..])
+    }
+    let (integer, mut s) = digits(s.strip_prefix('-').unwrap_or(s));
+    if integer.is_empty() || (integer.len() > 1 && integer.starts_with('0')) {
+        return false;
+    }
+    if let Some(rest) = s.strip_prefix('.') {
+        let (fraction, rest) = digits(rest);
+        if fraction.is_empty() {
+            return false;
+        }
+        s = rest;
+    }
+    if let Some(rest) = s.strip_prefix(&['e', 'E'][..]) {
+        let (exponent, rest) = digits(rest.strip_prefix(&['+', '-'][..]).unwrap_or(rest));
+        if exponent.is_empty() {
+            return false;
+        }
+        s = rest;
+    }
+    s.is_empty()
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, (usize, String)> {
+        let mut parser = Parser {
+            text,
+            chars: text.char_indices().peekable(),
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.chars.peek().is_some() {
+            return parser.error("unexpected text after JSON value");
+        }
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if ch < ' ' => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(ref n) => write!(f, "{}", n),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_number, Json};
+
+    fn string(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        let text = r#""a\"b\\c\/d\b\f\n\r\té😀""#;
+        assert_eq!(Json::parse(text), Ok(string("a\"b\\c/d\u{8}\u{c}\n\r\té\u{1f600}")));
+        assert_eq!(Json::parse(r#""\ud83d""#), Err((0, "invalid \\u escape".to_string())));
+        assert_eq!(Json::parse(r#""\ud83dx""#), Err((0, "invalid \\u escape".to_string())));
+        assert_eq!(Json::parse(r#""\ud83dA""#), Err((0, "invalid \\u escape".to_string())));
+        assert_eq!(Json::parse(r#""\ude00""#), Err((0, "invalid \\u escape".to_string())));
+        assert_eq!(Json::parse(r#""\u12g4""#), Err((0, "invalid \\u escape".to_string())));
+        assert_eq!(Json::parse(r#""\x""#), Err((0, "invalid escape".to_string())));
+        assert_eq!(Json::parse("\"a\nb\""), Err((1, "unterminated string".to_string())));
+        assert_eq!(Json::parse("\"ab"), Err((0, "unterminated string".to_string())));
+    }
+
+    #[test]
+    fn test_is_number() {
+        for &s in &["0", "-0", "12", "1.25", "-1.5e+3", "2E-2", "7e10"] {
+            assert!(is_number(s), "{}", s);
+        }
+        for &s in &["", "-", "01", "+1", "1.", ".5", "1e", "1e+", "1.2.3", "0x10", "1 "] {
+            assert!(!is_number(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_nested() {
+        let text = " {\"a\": [1, -2.5e3, {\"b\": null}, []],\n \"c\": {}, \"d\": [true, false]} ";
+        let expected = Json::Object(vec![
+            (
+                "a".to_string(),
+                Json::Array(vec![
+                    Json::Number("1".to_string()),
+                    Json::Number("-2.5e3".to_string()),
+                    Json::Object(vec![("b".to_string(), Json::Null)]),
+                    Json::Array(vec![]),
+                ]),
+            ),
+            ("c".to_string(), Json::Object(vec![])),
+            ("d".to_string(), Json::Array(vec![Json::Bool(true), Json::Bool(false)])),
+        ]);
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(json.get("c"), Some(&Json::Object(vec![])));
+        assert_eq!(json.get("e"), None);
+        assert_eq!(Json::parse(&json.to_string()), Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| Json::parse(text).unwrap_err();
+        assert_eq!(error(""), (0, "unexpected end of input".to_string()));
+        assert_eq!(error("[1,\n]"), (1, "unexpected character: ]".to_string()));
+        assert_eq!(error("[-]"), (0, "invalid number: -".to_string()));
+        assert_eq!(error("'a'"), (0, "unexpected character: '".to_string()));
+        assert_eq!(error("[1 2]"), (0, "expected , or ]".to_string()));
+        assert_eq!(error("{\"a\" 1}"), (0, "expected :".to_string()));
+        assert_eq!(error("{\"a\": 1,}"), (0, "expected \"".to_string()));
+        assert_eq!(error("{\"a\": 1]"), (0, "expected , or }".to_string()));
+        assert_eq!(error("[01]"), (0, "invalid number: 01".to_string()));
+        assert_eq!(error("nul"), (0, "expected null".to_string()));
+        assert_eq!(error("[]\n[]"), (1, "unexpected text after JSON value".to_string()));
+    }
+
+    #[test]
+    fn test_write_strings() {
+        let json = Json::Array(vec![string("a\"b\\c/\n\r\t\u{1}é\u{1f600}"), string("")]);
+        assert_eq!(json.to_string(), r#"["a\"b\\c/\n\r\t\u0001é😀",""]"#);
+        let json = Json::Object(vec![("k\"".to_string(), Json::Number("1".to_string()))]);
+        assert_eq!(json.to_string(), r#"{"k\"":1}"#);
+    }
+}
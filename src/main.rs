@@ -6,22 +6,326 @@ extern crate num_rational;
 extern crate vote;
 
 mod ballot_parser;
+mod json;
 
-use ballot_parser::parse_ballot_files;
+use ballot_parser::{parse_ballot_files, BallotParser, BallotType, Error, Format, Scores};
 use getopts::Options;
+use json::{is_number, Json};
 use std::env;
-use std::fmt::Display;
-use std::io::{stderr, Write};
+use std::fmt::{self, Display};
+use std::io::{self, stderr, stdout, Write};
+use std::iter::once;
 use std::process::exit;
 use std::str::FromStr;
-use vote::schulze_stv::schulze_stv;
+use vote::cardinal::{approval, approval_totals, score, score_totals, star};
+use vote::condorcet::{condorcet_winner, copeland, copeland_scores, minimax, minimax_scores,
+                      schwartz_set, smith_set};
+use vote::cpo_stv::{cpo_stv, cpo_stv_ranking, Completion};
+use vote::debian::{debian_dropped, Dropped};
+use vote::gregory_stv::{gregory_stv, GregoryAction, GregoryRules, GregoryStage};
+use vote::irv::{irv, IrvRound};
+use vote::kemeny_young::kemeny_young;
+use vote::meek::{meek_stv, MeekOptions, MeekRound};
+use vote::pairwise::{pairwise_defeat_groups, pairwise_matrix, DefeatStrength};
+use vote::proportional_approval::{equal_shares, pav, pav_score, phragmen, sequential_pav,
+                                  CommitteeStep};
+use vote::quota::Quota;
+use vote::ranked_pairs::{ranked_pairs, ranked_pairs_ranking};
+use vote::schulze::{path_strengths, path_winners, schulze_graph, schulze_ranking};
+use vote::schulze_proportional_ranking::schulze_proportional_ranking;
+use vote::schulze_stv::{schulze_stv, schulze_stv_ranking};
+use vote::split_cycle::{split_cycle, split_cycle_ranking};
 use vote::traits::{Weight, WeightOps};
 
 const USAGE: &'static str = include_str!("usage.txt");
 
+// Why elect stopped.
+enum CliError {
+    // Options that are malformed, or that do not go together.
+    Usage(String),
+    Ballots(Vec<Error>),
+    NoBallots,
+    // Whether there were candidates before withdrawals.
+    NoCandidates { withdrawn: bool },
+    // A method named in a ballot file.
+    UnknownMethod(String),
+    TooManyCandidates { method: &'static str, limit: usize },
+    Io(io::Error),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref message) => write!(f, "{}", message),
+            CliError::Ballots(ref errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|e| e.report("error"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            CliError::NoBallots => write!(f, "No ballots found"),
+            CliError::NoCandidates { withdrawn: false } => write!(f, "No candidates found"),
+            CliError::NoCandidates { withdrawn: true } => {
+                write!(f, "No candidates remain after withdrawals")
+            }
+            CliError::UnknownMethod(ref name) => write!(f, "unknown method {}", name),
+            CliError::TooManyCandidates { method, limit } => {
+                write!(f, "method {} supports at most {} candidates", method, limit)
+            }
+            CliError::Io(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
 struct Calc {
     calc: &'static str,
-    run: fn(&Calc, &str, usize, &[String]) -> Result<(), String>,
+    run: fn(&Calc, &str, &Config) -> Result<(), CliError>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Method {
+    SchulzeStv,
+    Schulze,
+    SchulzeProportionalRanking,
+    RankedPairs,
+    Meek,
+    ScottishStv,
+    Wigm,
+    Irv,
+    CpoStv,
+    KemenyYoung,
+    SplitCycle,
+    Approval,
+    Score,
+    Star,
+    Pav,
+    SequentialPav,
+    Phragmen,
+    EqualShares,
+}
+
+const METHODS: &[(&str, Method)] = &[
+    ("schulze-stv", Method::SchulzeStv),
+    ("schulze", Method::Schulze),
+    ("schulze-proportional", Method::SchulzeProportionalRanking),
+    ("ranked-pairs", Method::RankedPairs),
+    ("meek", Method::Meek),
+    ("scottish-stv", Method::ScottishStv),
+    ("wigm", Method::Wigm),
+    ("irv", Method::Irv),
+    ("cpo-stv", Method::CpoStv),
+    ("kemeny-young", Method::KemenyYoung),
+    ("split-cycle", Method::SplitCycle),
+    ("approval", Method::Approval),
+    ("score", Method::Score),
+    ("star", Method::Star),
+    ("pav", Method::Pav),
+    ("sequential-pav", Method::SequentialPav),
+    ("phragmen", Method::Phragmen),
+    ("equal-shares", Method::EqualShares),
+];
+
+impl Method {
+    fn title(self) -> &'static str {
+        match self {
+            Method::SchulzeStv => "Schulze STV",
+            Method::Schulze => "Schulze",
+            Method::SchulzeProportionalRanking => "Schulze proportional ranking",
+            Method::RankedPairs => "Ranked Pairs",
+            Method::Meek => "Meek STV",
+            Method::ScottishStv => "Scottish STV",
+            Method::Wigm => "Weighted Inclusive Gregory STV",
+            Method::Irv => "Instant-runoff voting",
+            Method::CpoStv => "CPO-STV",
+            Method::KemenyYoung => "Kemeny–Young",
+            Method::SplitCycle => "Split Cycle",
+            Method::Approval => "Approval voting",
+            Method::Score => "Score voting",
+            Method::Star => "STAR voting",
+            Method::Pav => "Proportional Approval Voting",
+            Method::SequentialPav => "Sequential Proportional Approval Voting",
+            Method::Phragmen => "Sequential Phragmén",
+            Method::EqualShares => "Method of Equal Shares",
+        }
+    }
+
+    fn single_winner(self) -> bool {
+        match self {
+            Method::Schulze
+            | Method::RankedPairs
+            | Method::Irv
+            | Method::KemenyYoung
+            | Method::SplitCycle
+            | Method::Approval
+            | Method::Score
+            | Method::Star => true,
+            Method::SchulzeStv
+            | Method::SchulzeProportionalRanking
+            | Method::Meek
+            | Method::ScottishStv
+            | Method::Wigm
+            | Method::CpoStv
+            | Method::Pav
+            | Method::SequentialPav
+            | Method::Phragmen
+            | Method::EqualShares => false,
+        }
+    }
+
+    fn ranks(self) -> bool {
+        match self {
+            Method::SchulzeStv
+            | Method::Schulze
+            | Method::RankedPairs
+            | Method::CpoStv
+            | Method::SplitCycle => true,
+            Method::SchulzeProportionalRanking
+            | Method::Meek
+            | Method::ScottishStv
+            | Method::Wigm
+            | Method::Irv
+            | Method::KemenyYoung
+            | Method::Approval
+            | Method::Score
+            | Method::Star
+            | Method::Pav
+            | Method::SequentialPav
+            | Method::Phragmen
+            | Method::EqualShares => false,
+        }
+    }
+
+    fn compares(self) -> bool {
+        match self {
+            Method::Schulze | Method::RankedPairs | Method::SplitCycle => true,
+            Method::SchulzeStv
+            | Method::SchulzeProportionalRanking
+            | Method::Meek
+            | Method::ScottishStv
+            | Method::Wigm
+            | Method::Irv
+            | Method::CpoStv
+            | Method::KemenyYoung
+            | Method::Approval
+            | Method::Score
+            | Method::Star
+            | Method::Pav
+            | Method::SequentialPav
+            | Method::Phragmen
+            | Method::EqualShares => false,
+        }
+    }
+
+    fn cardinal(self) -> bool {
+        match self {
+            Method::Approval
+            | Method::Score
+            | Method::Star
+            | Method::Pav
+            | Method::SequentialPav
+            | Method::Phragmen
+            | Method::EqualShares => true,
+            Method::SchulzeStv
+            | Method::Schulze
+            | Method::SchulzeProportionalRanking
+            | Method::RankedPairs
+            | Method::Meek
+            | Method::ScottishStv
+            | Method::Wigm
+            | Method::Irv
+            | Method::CpoStv
+            | Method::KemenyYoung
+            | Method::SplitCycle => false,
+        }
+    }
+}
+
+const FORMATS: &[(&str, Format)] = &[
+    ("line", Format::Line),
+    ("blt", Format::Blt),
+    ("preflib", Format::PrefLib),
+    ("abif", Format::Abif),
+    ("csv", Format::CsvRanks),
+    ("csv-choices", Format::CsvChoices),
+    ("debian-vote", Format::DebianVote),
+    ("debian-tally", Format::DebianTally),
+    ("json", Format::Json),
+];
+
+const WRITE_FORMATS: &[(&str, Format)] = &[("line", Format::Line), ("abif", Format::Abif)];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Text,
+    Json,
+}
+
+const OUTPUTS: &[(&str, Output)] = &[("text", Output::Text), ("json", Output::Json)];
+
+const BALLOT_TYPES: &[(&str, BallotType)] = &[
+    ("ranked", BallotType::Ranked),
+    ("approval", BallotType::Approval),
+    ("score", BallotType::Score),
+];
+
+const STRENGTHS: &[(&str, DefeatStrength)] = &[
+    ("winning-votes", DefeatStrength::WinningVotes),
+    ("margins", DefeatStrength::Margins),
+    ("ratio", DefeatStrength::Ratio),
+];
+
+// The exact Kemeny–Young solver takes O(2^n n^2) time.
+const MAX_KEMENY_YOUNG_CANDIDATES: usize = 10;
+
+// Invalid ballot lines to report before giving up.
+const MAX_ERRORS: usize = 20;
+
+const QUOTAS: &[(&str, Quota)] = &[("droop", Quota::Droop), ("hare", Quota::Hare)];
+
+const COMPLETIONS: &[(&str, Completion)] = &[
+    ("schulze", Completion::Schulze),
+    ("ranked-pairs", Completion::RankedPairs),
+];
+
+struct DebianRules {
+    // None looks for an option named None of the Above or Further Discussion.
+    default_option: Option<String>,
+    supermajority: String,
+    quorum: String,
+}
+
+struct Config {
+    method: Method,
+    // Whether the method was chosen on the command line, rather than by the
+    // ballot file.
+    method_given: bool,
+    ballot_type: BallotType,
+    // None picks the format from each file name.
+    format: Option<Format>,
+    // None uses the number of seats from the ballot file, if any.
+    num_seats: Option<usize>,
+    strength: DefeatStrength,
+    strength_given: bool,
+    quota: Quota,
+    completion: Completion,
+    tolerance: Option<String>,
+    rank: bool,
+    pairwise: bool,
+    explain: bool,
+    condorcet_summary: bool,
+    compare: Option<Vec<(String, Method)>>,
+    debian: Option<DebianRules>,
+    output: Output,
+    // Write the ballots in this format instead of tallying them.
+    write: Option<Format>,
+    // Stop after this many invalid ballot lines; 0 for no limit.
+    max_errors: usize,
+    // Tally the valid ballots instead of failing on invalid ones.
+    skip_invalid: bool,
+    filenames: Vec<String>,
 }
 
 const CALCS: &'static [Calc] = &[
@@ -41,8 +345,7 @@ const CALCS: &'static [Calc] = &[
     },
 ];
 
-fn main_result() -> Result<(), String> {
-    let args = env::args().collect::<Vec<_>>();
+fn main_result(args: &[String]) -> Result<(), CliError> {
     let program = &args[0];
 
     let mut opts = Options::new();
@@ -52,6 +355,95 @@ fn main_result() -> Result<(), String> {
         "elect an N-winner committee (default: 1)",
         "N",
     );
+    opts.optopt(
+        "",
+        "method",
+        &format!(
+            "voting method to use: {} (default: {})",
+            METHODS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            METHODS[0].0
+        ),
+        "METHOD",
+    );
+    opts.optopt(
+        "",
+        "format",
+        &format!(
+            "format of the ballot files: {} (default: from the file extension, else line)",
+            FORMATS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "FORMAT",
+    );
+    opts.optopt(
+        "",
+        "ballot-type",
+        &format!(
+            "syntax of the ballots: {} (default: {})",
+            BALLOT_TYPES
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            BALLOT_TYPES[0].0
+        ),
+        "TYPE",
+    );
+    opts.optopt(
+        "",
+        "strength",
+        &format!(
+            "measure of pairwise defeat strength for single-winner methods: {} (default: {})",
+            STRENGTHS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            STRENGTHS[0].0
+        ),
+        "STRENGTH",
+    );
+    opts.optopt(
+        "",
+        "quota",
+        &format!(
+            "quota for Meek STV, WIGM and CPO-STV: {} (default: {})",
+            QUOTAS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            QUOTAS[0].0
+        ),
+        "QUOTA",
+    );
+    opts.optopt(
+        "",
+        "completion",
+        &format!(
+            "Condorcet completion for CPO-STV: {} (default: {})",
+            COMPLETIONS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            COMPLETIONS[0].0
+        ),
+        "COMPLETION",
+    );
+    opts.optopt(
+        "",
+        "tolerance",
+        "total surplus below which Meek STV and CPO-STV stop iterating (default: 0.000001)",
+        "X",
+    );
     opts.optopt(
         "",
         "calc",
@@ -61,10 +453,110 @@ fn main_result() -> Result<(), String> {
         ),
         "TYPE",
     );
+    opts.optflag(
+        "",
+        "rank",
+        "rank all candidates (or winner sets) instead of only finding the winners",
+    );
+    opts.optflag(
+        "",
+        "pairwise",
+        "print the matrix of pairwise preferences between candidates",
+    );
+    opts.optflag(
+        "",
+        "explain",
+        "print the pairwise preferences and Schulze strongest path strengths",
+    );
+    opts.optopt(
+        "",
+        "compare",
+        &format!(
+            "compare the winners and rankings of several single-winner methods: {}",
+            METHODS
+                .iter()
+                .filter(|&&(_, method)| method.compares())
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "METHOD,...",
+    );
+    opts.optflag(
+        "",
+        "condorcet-summary",
+        "print the Condorcet winner, Smith and Schwartz sets, and Copeland and Minimax results",
+    );
+    opts.optflag(
+        "",
+        "debian-rules",
+        "drop options failing the quorum or majority against the default option, as in Debian \
+         (default method: schulze)",
+    );
+    opts.optopt(
+        "",
+        "default-option",
+        "the default option for --debian-rules (default: the option named None of the Above \
+         or Further Discussion)",
+        "NAME",
+    );
+    opts.optopt(
+        "",
+        "supermajority",
+        "the majority needed over the default option for --debian-rules (default: 1:1)",
+        "N:M",
+    );
+    opts.optopt(
+        "",
+        "quorum",
+        "the votes needed over the default option for --debian-rules (default: 0)",
+        "VOTES",
+    );
+    opts.optopt(
+        "",
+        "output",
+        &format!(
+            "format of the results: {} (default: {})",
+            OUTPUTS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            OUTPUTS[0].0
+        ),
+        "FORMAT",
+    );
+    opts.optopt(
+        "",
+        "write",
+        &format!(
+            "write the ballots to stdout instead of tallying them: {}",
+            WRITE_FORMATS
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "FORMAT",
+    );
+    opts.optopt(
+        "",
+        "max-errors",
+        &format!(
+            "stop after this many invalid ballot lines, or 0 for no limit (default: {})",
+            MAX_ERRORS
+        ),
+        "N",
+    );
+    opts.optflag(
+        "",
+        "skip-invalid",
+        "leave invalid ballot lines out of the tally instead of stopping",
+    );
     opts.optflag("", "help", "show this help message and exit");
     opts.optflag("", "version", "show the program version and exit");
     let matches = opts.parse(&args[1..])
-        .map_err(|e| format!("{}: error: {}", program, e))?;
+        .map_err(|e| CliError::Usage(e.to_string()))?;
 
     if matches.opt_present("help") {
         print!("{}", opts.usage(USAGE));
@@ -85,32 +577,361 @@ fn main_result() -> Result<(), String> {
         .opt_str("w")
         .map(|s| {
             s.parse()
-                .map_err(|e| format!("{}: error: -w argument: {}", program, e))
+                .map_err(|e| CliError::Usage(format!("-w argument: {}", e)))
+        })
+        .map_or(Ok(None), |n| n.map(Some))?;
+
+    let max_errors = matches
+        .opt_str("max-errors")
+        .map(|s| {
+            s.parse()
+                .map_err(|e| CliError::Usage(format!("--max-errors argument: {}", e)))
         })
-        .unwrap_or(Ok(1))?;
+        .unwrap_or(Ok(MAX_ERRORS))?;
 
     let calc = match matches.opt_str("calc") {
         Some(calc_opt) => CALCS
             .iter()
             .find(|calc| calc.calc == calc_opt)
-            .ok_or_else(|| format!("unknown number type {}", calc_opt)),
+            .ok_or_else(|| CliError::Usage(format!("unknown number type {}", calc_opt))),
         None => Ok(&CALCS[0]),
     }?;
-    (calc.run)(calc, program, num_seats, &matches.free)
+    let method = match matches.opt_str("method") {
+        Some(method_opt) => METHODS
+            .iter()
+            .find(|&&(name, _)| name == method_opt)
+            .map(|&(_, method)| method)
+            .ok_or_else(|| CliError::Usage(format!("unknown method {}", method_opt))),
+        None if matches.opt_present("debian-rules") => Ok(Method::Schulze),
+        None => Ok(METHODS[0].1),
+    }?;
+    let format = match matches.opt_str("format") {
+        Some(format_opt) => Some(
+            FORMATS
+                .iter()
+                .find(|&&(name, _)| name == format_opt)
+                .map(|&(_, format)| format)
+                .ok_or_else(|| CliError::Usage(format!("unknown format {}", format_opt)))?,
+        ),
+        None => None,
+    };
+    let write = match matches.opt_str("write") {
+        Some(write_opt) => Some(
+            WRITE_FORMATS
+                .iter()
+                .find(|&&(name, _)| name == write_opt)
+                .map(|&(_, format)| format)
+                .ok_or_else(|| CliError::Usage(format!("cannot write format {}", write_opt)))?,
+        ),
+        None => None,
+    };
+    let ballot_type = match matches.opt_str("ballot-type") {
+        Some(ballot_type_opt) => BALLOT_TYPES
+            .iter()
+            .find(|&&(name, _)| name == ballot_type_opt)
+            .map(|&(_, ballot_type)| ballot_type)
+            .ok_or_else(|| CliError::Usage(format!("unknown ballot type {}", ballot_type_opt))),
+        None => Ok(BALLOT_TYPES[0].1),
+    }?;
+    if let Some(name) = matches.opt_str("method") {
+        check_method(
+            &name,
+            method,
+            matches.opt_present("rank"),
+            num_seats,
+            ballot_type,
+        )?;
+    }
+    let compare = match matches.opt_str("compare") {
+        Some(compare_opt) => Some(
+            compare_opt
+                .split(',')
+                .map(|name| {
+                    METHODS
+                        .iter()
+                        .find(|&&(name1, method)| name1 == name && method.compares())
+                        .map(|&(_, method)| (name.to_string(), method))
+                        .ok_or_else(|| CliError::Usage(format!("cannot compare method {}", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+    if compare.is_some() {
+        if matches.opt_present("method") || matches.opt_present("rank") {
+            return Err(CliError::Usage(
+                "--compare cannot be combined with --method or --rank".to_string(),
+            ));
+        }
+        if num_seats.unwrap_or(1) != 1 {
+            return Err(CliError::Usage(
+                "--compare only supports single-winner methods".to_string(),
+            ));
+        }
+    }
+    let debian = if matches.opt_present("debian-rules") {
+        if !method.compares() || compare.is_some() {
+            return Err(CliError::Usage(format!(
+                "--debian-rules needs method {}",
+                METHODS
+                    .iter()
+                    .filter(|&&(_, method)| method.compares())
+                    .map(|&(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        Some(DebianRules {
+            default_option: matches.opt_str("default-option"),
+            supermajority: matches
+                .opt_str("supermajority")
+                .unwrap_or_else(|| "1:1".to_string()),
+            quorum: matches.opt_str("quorum").unwrap_or_else(|| "0".to_string()),
+        })
+    } else {
+        if ["default-option", "supermajority", "quorum"]
+            .iter()
+            .any(|&name| matches.opt_present(name))
+        {
+            return Err(CliError::Usage(
+                "--default-option, --supermajority and --quorum need --debian-rules".to_string(),
+            ));
+        }
+        None
+    };
+    let output = match matches.opt_str("output") {
+        Some(output_opt) => OUTPUTS
+            .iter()
+            .find(|&&(name, _)| name == output_opt)
+            .map(|&(_, output)| output)
+            .ok_or_else(|| CliError::Usage(format!("unknown output format {}", output_opt))),
+        None => Ok(OUTPUTS[0].1),
+    }?;
+    if output == Output::Json
+        && ["pairwise", "explain", "condorcet-summary", "compare", "debian-rules", "write"]
+            .iter()
+            .any(|&name| matches.opt_present(name))
+    {
+        return Err(CliError::Usage(
+            "--output json cannot be combined with --pairwise, --explain, \
+             --condorcet-summary, --compare, --debian-rules or --write"
+                .to_string(),
+        ));
+    }
+    let strength = match matches.opt_str("strength") {
+        Some(strength_opt) => STRENGTHS
+            .iter()
+            .find(|&&(name, _)| name == strength_opt)
+            .map(|&(_, strength)| strength)
+            .ok_or_else(|| CliError::Usage(format!("unknown defeat strength {}", strength_opt))),
+        None => Ok(STRENGTHS[0].1),
+    }?;
+    let quota = match matches.opt_str("quota") {
+        Some(quota_opt) => QUOTAS
+            .iter()
+            .find(|&&(name, _)| name == quota_opt)
+            .map(|&(_, quota)| quota)
+            .ok_or_else(|| CliError::Usage(format!("unknown quota {}", quota_opt))),
+        None => Ok(QUOTAS[0].1),
+    }?;
+    let completion = match matches.opt_str("completion") {
+        Some(completion_opt) => COMPLETIONS
+            .iter()
+            .find(|&&(name, _)| name == completion_opt)
+            .map(|&(_, completion)| completion)
+            .ok_or_else(|| CliError::Usage(format!("unknown completion {}", completion_opt))),
+        None => Ok(COMPLETIONS[0].1),
+    }?;
+
+    let config = Config {
+        method,
+        ballot_type,
+        format,
+        num_seats,
+        strength,
+        strength_given: matches.opt_present("strength"),
+        quota,
+        completion,
+        tolerance: matches.opt_str("tolerance"),
+        rank: matches.opt_present("rank"),
+        pairwise: matches.opt_present("pairwise"),
+        explain: matches.opt_present("explain"),
+        condorcet_summary: matches.opt_present("condorcet-summary"),
+        method_given: matches.opt_present("method") || compare.is_some() || debian.is_some(),
+        compare,
+        debian,
+        output,
+        write,
+        max_errors,
+        skip_invalid: matches.opt_present("skip-invalid"),
+        filenames: matches.free.clone(),
+    };
+    (calc.run)(calc, program, &config)
+}
+
+enum Outcome {
+    // Tied winner sets.
+    Winners(Box<[Box<[usize]>]>),
+    // Tied winner sets at each position.
+    Ranking(Vec<Box<[Box<[usize]>]>>),
+    // Tied rankings of all candidates.
+    ProportionalRankings(Box<[Box<[usize]>]>),
 }
 
-fn run<W>(calc: &Calc, program: &str, num_seats: usize, filenames: &[String]) -> Result<(), String>
+fn check_method(
+    name: &str,
+    method: Method,
+    rank: bool,
+    num_seats: Option<usize>,
+    ballot_type: BallotType,
+) -> Result<(), CliError> {
+    if rank && !method.ranks() {
+        return Err(CliError::Usage(format!(
+            "--rank is not supported by method {}",
+            name
+        )));
+    }
+    if method.single_winner() && num_seats.unwrap_or(1) != 1 {
+        return Err(CliError::Usage(format!(
+            "method {} elects a single winner",
+            name
+        )));
+    }
+    if method.cardinal() && ballot_type == BallotType::Ranked {
+        return Err(CliError::Usage(format!(
+            "method {} needs --ballot-type approval or score",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn sort_sets(candidates: &[String], sets: &mut [Box<[usize]>]) {
+    for set in &mut *sets {
+        set.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    }
+    sets.sort_by(|a, b| {
+        a.iter()
+            .map(|&i| &candidates[i])
+            .cmp(b.iter().map(|&i| &candidates[i]))
+    });
+}
+
+fn singletons(candidates: &[usize]) -> Box<[Box<[usize]>]> {
+    candidates
+        .iter()
+        .map(|&c| vec![c].into_boxed_slice())
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+fn format_set(candidates: &[String], set: &[usize]) -> String {
+    set.iter()
+        .map(|&c| &candidates[c][..])
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_table(headers: &[String], rows: &[(String, Vec<String>)]) {
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(j, header)| {
+            rows.iter()
+                .map(|(_, cells)| cells[j].chars().count())
+                .chain(once(header.chars().count()))
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    print!("  {:1$}", "", name_width);
+    for (header, &width) in headers.iter().zip(&widths) {
+        print!("  {:>1$}", header, width);
+    }
+    println!();
+    for (name, cells) in rows {
+        print!("  {:1$}", name, name_width);
+        for (cell, &width) in cells.iter().zip(&widths) {
+            print!("  {:>1$}", cell, width);
+        }
+        println!();
+    }
+}
+
+fn print_matrix<W: Display>(candidates: &[String], matrix: &[Box<[W]>]) {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    let rows = order
+        .iter()
+        .map(|&a| {
+            let cells = order
+                .iter()
+                .map(|&b| if a == b {
+                    "-".to_string()
+                } else {
+                    matrix[a][b].to_string()
+                })
+                .collect();
+            (candidates[a].clone(), cells)
+        })
+        .collect::<Vec<_>>();
+    let headers = order
+        .iter()
+        .map(|&b| candidates[b].clone())
+        .collect::<Vec<_>>();
+    print_table(&headers, &rows);
+}
+
+fn meek_options<W>(config: &Config) -> Result<MeekOptions<W>, CliError>
 where
-    W: Display + FromStr + Weight,
+    W: FromStr + Weight,
     W::Err: Display,
-    for<'w> &'w W: WeightOps<W>,
 {
-    let bp = parse_ballot_files::<W, _>(filenames)?;
-    if bp.ballots.is_empty() {
-        return Err(format!("{}: error: No ballots found", program));
+    let mut options = MeekOptions {
+        quota: config.quota,
+        ..MeekOptions::default()
+    };
+    if let Some(ref tolerance) = config.tolerance {
+        options.tolerance = parse_number(tolerance)
+            .map_err(|e| CliError::Usage(format!("--tolerance argument: {}", e)))?;
+    }
+    Ok(options)
+}
+
+fn print_ballots<W: Display + Weight>(
+    calc: &Calc,
+    config: &Config,
+    method: Method,
+    bp: &BallotParser<W>,
+    total_weight: &W,
+) {
+    if let Some(ref title) = bp.title {
+        println!("{}", title);
+        println!();
     }
 
-    println!("Tallying Schulze STV election (calc={}).", calc.calc);
+    match config.compare {
+        Some(ref methods) => println!(
+            "Comparing {} elections (calc={}).",
+            methods
+                .iter()
+                .map(|&(_, method)| method.title())
+                .collect::<Vec<_>>()
+                .join(", "),
+            calc.calc
+        ),
+        None => println!(
+            "Tallying {} election (calc={}).",
+            method.title(),
+            calc.calc
+        ),
+    }
     println!("");
 
     println!("Candidates ({}):", bp.candidates.len());
@@ -121,11 +942,33 @@ where
     }
     println!("");
 
-    let total_weight = bp.ballots
-        .iter()
-        .fold(W::zero(), |acc, &(_, ref w)| acc + w);
+    if !bp.withdrawn.is_empty() {
+        println!("Withdrawn candidates ({}):", bp.withdrawn.len());
+        let mut withdrawn_sorted = bp.withdrawn.clone();
+        withdrawn_sorted.sort();
+        for candidate in &withdrawn_sorted {
+            println!("  {}", candidate);
+        }
+        println!();
+    }
+
     println!("Ballots ({}):", total_weight);
-    for &(ref groups, ref w) in &bp.ballots {
+    for (scores, w) in &bp.scores {
+        println!(
+            "  {}: {}",
+            w,
+            scores
+                .iter()
+                .map(|&(c, ref score)| if config.ballot_type == BallotType::Approval {
+                    bp.candidates[c].clone()
+                } else {
+                    format!("{}={}", bp.candidates[c], score)
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    for &(ref groups, ref w) in bp.ballots.iter().filter(|_| bp.scores.is_empty()) {
         println!(
             "  {}: {}",
             w,
@@ -143,17 +986,831 @@ where
         );
     }
     println!("");
+}
 
-    let mut winners = schulze_stv(bp.candidates.len(), num_seats, &bp.ballots);
+fn run<W>(calc: &Calc, program: &str, config: &Config) -> Result<(), CliError>
+where
+    W: Display + FromStr + Weight,
+    W::Err: Display,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let bp = parse_ballot_files::<W, _>(
+        &config.filenames,
+        config.ballot_type,
+        config.format,
+        config.max_errors,
+        config.skip_invalid,
+    )
+    .map_err(CliError::Ballots)?;
+    if !bp.errors.is_empty() {
+        let mut stderr = stderr();
+        for e in &bp.errors {
+            writeln!(&mut stderr, "{}", e.report("warning")).expect("failed printing to stderr");
+        }
+        let num_ballots = bp.errors.len() - bp.directive_errors;
+        if num_ballots != 0 {
+            writeln!(
+                &mut stderr,
+                "{}: warning: invalid ballot lines skipped: {}",
+                program, num_ballots
+            )
+            .expect("failed printing to stderr");
+        }
+        if bp.directive_errors != 0 {
+            writeln!(
+                &mut stderr,
+                "{}: warning: invalid directives skipped: {}",
+                program, bp.directive_errors
+            )
+            .expect("failed printing to stderr");
+        }
+    }
+    if bp.ballots.is_empty() {
+        return Err(CliError::NoBallots);
+    }
+    if bp.candidates.is_empty() {
+        return Err(CliError::NoCandidates {
+            withdrawn: !bp.withdrawn.is_empty(),
+        });
+    }
+    if let Some(format) = config.write {
+        let stdout = stdout();
+        return bp
+            .write_ballots(format, &mut stdout.lock())
+            .map_err(CliError::Io);
+    }
+    let method = match bp.method {
+        Some(ref name) if !config.method_given => {
+            let method = METHODS
+                .iter()
+                .find(|&&(name1, _)| name1 == name)
+                .map(|&(_, method)| method)
+                .ok_or_else(|| CliError::UnknownMethod(name.clone()))?;
+            let num_seats = config.num_seats.or(bp.num_seats);
+            check_method(name, method, config.rank, num_seats, config.ballot_type)?;
+            method
+        }
+        _ => config.method,
+    };
+    // Only the Condorcet methods, and the pairwise reports, use defeat strengths.
+    if config.strength_given
+        && !method.compares()
+        && config.compare.is_none()
+        && !config.explain
+        && !config.condorcet_summary
+    {
+        let name = METHODS
+            .iter()
+            .find(|&&(_, method1)| method1 == method)
+            .map(|&(name, _)| name)
+            .unwrap();
+        return Err(CliError::Usage(format!(
+            "--strength is not supported by method {}",
+            name
+        )));
+    }
+    let num_seats = match config.num_seats {
+        Some(num_seats) => num_seats,
+        None if method.single_winner() || config.compare.is_some() => 1,
+        None => bp.num_seats.unwrap_or(1),
+    };
+    let total_weight = bp.ballots
+        .iter()
+        .fold(W::zero(), |acc, &(_, ref w)| acc + w);
+    let text = config.output == Output::Text;
+    if text {
+        print_ballots(calc, config, method, &bp, &total_weight);
+    }
 
-    for set in &mut *winners {
-        set.sort_by(|&a, &b| bp.candidates[a].cmp(&bp.candidates[b]));
+    if config.pairwise || config.explain {
+        let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+        println!("Pairwise preferences (row over column):");
+        print_matrix(&bp.candidates, &d);
+        println!();
+
+        if config.explain {
+            let p = path_strengths(&d, config.strength);
+            println!("Strongest path strengths (row to column):");
+            print_matrix(&bp.candidates, &p);
+            println!();
+
+            let mut winners = path_winners(&p);
+            winners.sort_by(|&a, &b| bp.candidates[a].cmp(&bp.candidates[b]));
+            println!("Beating or tying every other candidate by path strength:");
+            println!("  {}", format_set(&bp.candidates, &winners));
+            println!();
+        }
     }
-    winners.sort_by(|a, b| {
-        a.iter()
-            .map(|&i| &bp.candidates[i])
-            .cmp(b.iter().map(|&i| &bp.candidates[i]))
-    });
+
+    if config.condorcet_summary {
+        let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+        print_condorcet_summary(&bp.candidates, &d, config.strength);
+    }
+
+    if let Some(ref methods) = config.compare {
+        let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+        let rankings = methods
+            .iter()
+            .map(|&(_, method)| condorcet_ranking(method, &d, config.strength))
+            .collect::<Vec<_>>();
+        print_comparison(&bp.candidates, methods, &rankings);
+        return Ok(());
+    }
+
+    if let Some(ref rules) = config.debian {
+        let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+        return print_debian(&bp.candidates, &d, config, rules);
+    }
+
+    let outcome = match method {
+        Method::SchulzeStv => if config.rank {
+            Outcome::Ranking(schulze_stv_ranking(bp.candidates.len(), num_seats, &bp.ballots))
+        } else {
+            Outcome::Winners(schulze_stv(bp.candidates.len(), num_seats, &bp.ballots))
+        },
+        Method::Schulze => {
+            let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+            let defeat_groups = pairwise_defeat_groups(&d, config.strength);
+            if config.rank {
+                let ranking = schulze_ranking(bp.candidates.len(), &defeat_groups);
+                Outcome::Ranking(ranking.iter().map(|group| singletons(group)).collect())
+            } else {
+                Outcome::Winners(singletons(&schulze_graph(bp.candidates.len(), &defeat_groups)))
+            }
+        }
+        Method::RankedPairs => {
+            let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+            let defeat_groups = pairwise_defeat_groups(&d, config.strength);
+            if config.rank {
+                let ranking = ranked_pairs_ranking(bp.candidates.len(), &defeat_groups);
+                Outcome::Ranking(ranking.iter().map(|group| singletons(group)).collect())
+            } else {
+                Outcome::Winners(singletons(&ranked_pairs(bp.candidates.len(), &defeat_groups)))
+            }
+        }
+        Method::Meek => {
+            let options = meek_options(config)?;
+            let result = meek_stv(bp.candidates.len(), num_seats, &bp.ballots, &options);
+            if text {
+                print_meek_rounds(&bp.candidates, &result.rounds);
+            }
+            Outcome::Winners(Box::new([result.elected]))
+        }
+        Method::ScottishStv | Method::Wigm => {
+            let rules = if method == Method::ScottishStv {
+                GregoryRules::Scottish
+            } else {
+                GregoryRules::Wigm(config.quota)
+            };
+            let result = gregory_stv(bp.candidates.len(), num_seats, &bp.ballots, rules);
+            if text {
+                println!("Quota: {}", result.quota);
+                println!();
+                print_gregory_stages(&bp.candidates, &result.stages);
+            }
+            Outcome::Winners(Box::new([result.elected]))
+        }
+        Method::CpoStv => {
+            let options = meek_options(config)?;
+            let (n, completion) = (bp.candidates.len(), config.completion);
+            if config.rank {
+                Outcome::Ranking(cpo_stv_ranking(n, num_seats, &bp.ballots, completion, &options))
+            } else {
+                Outcome::Winners(cpo_stv(n, num_seats, &bp.ballots, completion, &options))
+            }
+        }
+        Method::SplitCycle => {
+            let d = pairwise_matrix(bp.candidates.len(), &bp.ballots);
+            if config.rank {
+                let ranking = split_cycle_ranking(&d, config.strength);
+                Outcome::Ranking(ranking.iter().map(|group| singletons(group)).collect())
+            } else {
+                Outcome::Winners(singletons(&split_cycle(&d, config.strength)))
+            }
+        }
+        Method::KemenyYoung => {
+            if bp.candidates.len() > MAX_KEMENY_YOUNG_CANDIDATES {
+                return Err(CliError::TooManyCandidates {
+                    method: "kemeny-young",
+                    limit: MAX_KEMENY_YOUNG_CANDIDATES,
+                });
+            }
+            let result = kemeny_young(bp.candidates.len(), &bp.ballots);
+            if text {
+                if result.rankings.len() == 1 {
+                    println!("Kemeny–Young ranking (score {}):", result.score);
+                } else {
+                    println!("Tied Kemeny–Young rankings (score {}):", result.score);
+                }
+                for ranking in &*result.rankings {
+                    println!(
+                        "  {}",
+                        ranking
+                            .iter()
+                            .map(|&c| &bp.candidates[c][..])
+                            .collect::<Vec<_>>()
+                            .join(" > ")
+                    );
+                }
+                println!();
+            }
+
+            let mut winners = result.rankings.iter().map(|r| r[0]).collect::<Vec<_>>();
+            winners.sort();
+            winners.dedup();
+            Outcome::Winners(singletons(&winners))
+        }
+        Method::Approval => {
+            let approved = approval_ballots(&bp.scores);
+            if text {
+                let totals = approval_totals(bp.candidates.len(), &approved);
+                print_totals(&bp.candidates, "Approvals", &totals);
+            }
+            Outcome::Winners(singletons(&approval(bp.candidates.len(), &approved)))
+        }
+        Method::Score => {
+            if text {
+                let totals = score_totals(bp.candidates.len(), &bp.scores);
+                print_totals(&bp.candidates, "Score", &totals);
+            }
+            Outcome::Winners(singletons(&score(bp.candidates.len(), &bp.scores)))
+        }
+        Method::Star => {
+            let result = star(bp.candidates.len(), &bp.scores);
+            if text {
+                print_totals(&bp.candidates, "Score", &result.totals);
+                let mut finalists = result.finalists.to_vec();
+                finalists.sort_by(|&a, &b| bp.candidates[a].cmp(&bp.candidates[b]));
+                println!("Runoff (ballots preferring row over column):");
+                let runoff = finalists
+                    .iter()
+                    .map(|&a| {
+                        finalists
+                            .iter()
+                            .map(|&b| result.runoff[a][b].clone())
+                            .collect::<Vec<_>>()
+                            .into_boxed_slice()
+                    })
+                    .collect::<Vec<_>>();
+                let names = finalists
+                    .iter()
+                    .map(|&c| bp.candidates[c].clone())
+                    .collect::<Vec<_>>();
+                print_matrix(&names, &runoff);
+                println!();
+            }
+            Outcome::Winners(singletons(&result.winners))
+        }
+        Method::Pav => {
+            let approved = approval_ballots(&bp.scores);
+            let winners = pav(bp.candidates.len(), num_seats, &approved);
+            if text && !winners.is_empty() {
+                println!("PAV score: {}", pav_score(&winners[0], &approved));
+                println!();
+            }
+            Outcome::Winners(winners)
+        }
+        Method::SequentialPav | Method::Phragmen => {
+            let approved = approval_ballots(&bp.scores);
+            let (steps, value) = if method == Method::SequentialPav {
+                (sequential_pav(bp.candidates.len(), num_seats, &approved), "gain")
+            } else {
+                (phragmen(bp.candidates.len(), num_seats, &approved), "load")
+            };
+            if text {
+                print_committee_steps(&bp.candidates, &steps, value, &[]);
+            }
+            let winners = steps.iter().map(|step| step.candidate).collect::<Vec<_>>();
+            Outcome::Winners(Box::new([winners.into_boxed_slice()]))
+        }
+        Method::EqualShares => {
+            let approved = approval_ballots(&bp.scores);
+            let result = equal_shares(bp.candidates.len(), num_seats, &approved);
+            if text {
+                print_committee_steps(&bp.candidates, &result.steps, "price", &result.completion);
+            }
+            let winners = result.steps.iter().map(|step| step.candidate).collect::<Vec<_>>();
+            Outcome::Winners(Box::new([winners.into_boxed_slice()]))
+        }
+        Method::Irv => {
+            let result = irv(bp.candidates.len(), &bp.ballots);
+            if text {
+                print_irv_rounds(&bp.candidates, &result.rounds);
+            }
+            Outcome::Winners(singletons(&result.winners))
+        }
+        Method::SchulzeProportionalRanking => Outcome::ProportionalRankings(
+            schulze_proportional_ranking(bp.candidates.len(), num_seats, &bp.ballots),
+        ),
+    };
+
+    match config.output {
+        Output::Text => print_outcome(&bp.candidates, num_seats, outcome),
+        Output::Json => print_json(&bp, method, num_seats, &total_weight, outcome),
+    }
+    Ok(())
+}
+
+fn print_outcome(candidates: &[String], num_seats: usize, outcome: Outcome) {
+    match outcome {
+        Outcome::Winners(mut winners) => print_winners(candidates, num_seats, &mut winners),
+        Outcome::Ranking(mut ranking) => print_ranking(candidates, num_seats, &mut ranking),
+        Outcome::ProportionalRankings(rankings) => if rankings.len() == 1 {
+            println!("Proportional ranking:");
+            for (i, &c) in rankings[0].iter().enumerate() {
+                println!("  {}. {}", i + 1, candidates[c]);
+            }
+        } else {
+            println!("Tied proportional rankings:");
+            for ranking in &*rankings {
+                println!(
+                    "  {}",
+                    ranking
+                        .iter()
+                        .map(|&c| &candidates[c][..])
+                        .collect::<Vec<_>>()
+                        .join(" > ")
+                );
+            }
+        },
+    }
+}
+
+fn json_weight<W: Display>(w: &W) -> Json {
+    let w = w.to_string();
+    if is_number(&w) {
+        Json::Number(w)
+    } else {
+        Json::String(w)
+    }
+}
+
+fn json_names(candidates: &[String], set: &[usize]) -> Json {
+    Json::Array(
+        set.iter()
+            .map(|&c| Json::String(candidates[c].clone()))
+            .collect(),
+    )
+}
+
+fn json_sets(candidates: &[String], sets: &mut [Box<[usize]>]) -> Json {
+    sort_sets(candidates, sets);
+    Json::Array(sets.iter().map(|set| json_names(candidates, set)).collect())
+}
+
+fn print_json<W: Display + Weight>(
+    bp: &BallotParser<W>,
+    method: Method,
+    num_seats: usize,
+    total_weight: &W,
+    outcome: Outcome,
+) {
+    let name = METHODS
+        .iter()
+        .find(|&&(_, method1)| method1 == method)
+        .map(|&(name, _)| name)
+        .unwrap();
+    let mut candidates_sorted = (0..bp.candidates.len()).collect::<Vec<_>>();
+    candidates_sorted.sort_by(|&a, &b| bp.candidates[a].cmp(&bp.candidates[b]));
+    let mut withdrawn_sorted = bp.withdrawn.clone();
+    withdrawn_sorted.sort();
+    let mut members = vec![
+        (
+            "title".to_string(),
+            match bp.title {
+                Some(ref title) => Json::String(title.clone()),
+                None => Json::Null,
+            },
+        ),
+        ("method".to_string(), Json::String(name.to_string())),
+        ("seats".to_string(), Json::Number(num_seats.to_string())),
+        (
+            "candidates".to_string(),
+            json_names(&bp.candidates, &candidates_sorted),
+        ),
+        (
+            "withdrawn".to_string(),
+            Json::Array(withdrawn_sorted.into_iter().map(Json::String).collect()),
+        ),
+        ("ballots".to_string(), json_weight(total_weight)),
+    ];
+    match outcome {
+        Outcome::Winners(mut winners) => {
+            let tied = winners.len() > 1;
+            members.push(("winners".to_string(), json_sets(&bp.candidates, &mut winners)));
+            members.push(("tied".to_string(), Json::Bool(tied)));
+        }
+        Outcome::Ranking(mut ranking) => {
+            let ranking = ranking
+                .iter_mut()
+                .map(|sets| json_sets(&bp.candidates, sets))
+                .collect();
+            members.push(("ranking".to_string(), Json::Array(ranking)));
+        }
+        Outcome::ProportionalRankings(rankings) => {
+            let tied = rankings.len() > 1;
+            let rankings = rankings
+                .iter()
+                .map(|ranking| json_names(&bp.candidates, ranking))
+                .collect();
+            members.push(("rankings".to_string(), Json::Array(rankings)));
+            members.push(("tied".to_string(), Json::Bool(tied)));
+        }
+    }
+    println!("{}", Json::Object(members));
+}
+
+// Parses a number, also accepting decimals like 0.001 with the rational number types.
+fn parse_number<W>(s: &str) -> Result<W, W::Err>
+where
+    W: FromStr + Weight,
+{
+    W::from_str(s).or_else(|e| {
+        let (int, frac) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => return Err(e),
+        };
+        if !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(e);
+        }
+        match (
+            (int.to_string() + frac).parse::<i64>(),
+            10i64.checked_pow(frac.len() as u32),
+        ) {
+            (Ok(n), Some(scale)) => Ok(W::from_i64(n) / W::from_i64(scale)),
+            _ => Err(e),
+        }
+    })
+}
+
+fn print_meek_rounds<W: Display + Weight>(candidates: &[String], rounds: &[MeekRound<W>]) {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    for (i, round) in rounds.iter().enumerate() {
+        println!(
+            "Round {} (quota {}, exhausted {}):",
+            i + 1,
+            round.quota,
+            round.exhausted
+        );
+        for &c in &order {
+            if round.keep_factors[c] != W::zero() {
+                println!(
+                    "  {}: {} (keep factor {})",
+                    candidates[c],
+                    round.votes[c],
+                    round.keep_factors[c]
+                );
+            }
+        }
+        for &c in &*round.elected {
+            println!("  Elected: {}", candidates[c]);
+        }
+        if let Some(c) = round.excluded {
+            if round.tied {
+                println!("  Excluded: {} (tie broken)", candidates[c]);
+            } else {
+                println!("  Excluded: {}", candidates[c]);
+            }
+        }
+        println!();
+    }
+}
+
+fn print_gregory_stages<W: Display + Weight>(candidates: &[String], stages: &[GregoryStage<W>]) {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    for (i, stage) in stages.iter().enumerate() {
+        let action = match stage.action {
+            GregoryAction::FirstPreferences => "first preferences".to_string(),
+            GregoryAction::Surplus(c) => format!("surplus of {}", candidates[c]),
+            GregoryAction::Exclusion(c) => format!("exclusion of {}", candidates[c]),
+        };
+        if stage.tied {
+            println!("Stage {} ({}, tie broken):", i + 1, action);
+        } else {
+            println!("Stage {} ({}):", i + 1, action);
+        }
+        for &c in &order {
+            if stage.votes[c] != W::zero() {
+                println!("  {}: {}", candidates[c], stage.votes[c]);
+            }
+        }
+        println!("  Non-transferable: {}", stage.non_transferable);
+        if stage.lost != W::zero() {
+            println!("  Lost to rounding: {}", stage.lost);
+        }
+        for &c in &*stage.elected {
+            println!("  Elected: {}", candidates[c]);
+        }
+        println!();
+    }
+}
+
+fn condorcet_ranking<W>(
+    method: Method,
+    d: &[Box<[W]>],
+    strength: DefeatStrength,
+) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    match method {
+        Method::Schulze => schulze_ranking(d.len(), &pairwise_defeat_groups(d, strength)),
+        Method::RankedPairs => ranked_pairs_ranking(d.len(), &pairwise_defeat_groups(d, strength)),
+        Method::SplitCycle => split_cycle_ranking(d, strength),
+        _ => unreachable!(),
+    }
+}
+
+fn print_debian<W>(
+    candidates: &[String],
+    d: &[Box<[W]>],
+    config: &Config,
+    rules: &DebianRules,
+) -> Result<(), CliError>
+where
+    W: Display + FromStr + Weight,
+    W::Err: Display,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let default = match rules.default_option {
+        Some(ref name) => candidates
+            .iter()
+            .position(|c| c == name)
+            .ok_or_else(|| CliError::Usage(format!("unknown default option {}", name)))?,
+        None => candidates
+            .iter()
+            .position(|c| {
+                let c = c.to_lowercase();
+                c.contains("none of the above") || c.contains("further discussion")
+            })
+            .ok_or_else(|| {
+                CliError::Usage("no default option found, use --default-option".to_string())
+            })?,
+    };
+    let parse = |s: &str, name: &str| {
+        W::from_str(s.trim()).map_err(|e| CliError::Usage(format!("--{} argument: {}", name, e)))
+    };
+    let (n, m) = match rules.supermajority.find(':') {
+        Some(i) => (&rules.supermajority[..i], &rules.supermajority[i + 1..]),
+        None => (&rules.supermajority[..], "1"),
+    };
+    let (n, m) = (parse(n, "supermajority")?, parse(m, "supermajority")?);
+    if n <= W::zero() || m <= W::zero() {
+        return Err(CliError::Usage(
+            "--supermajority argument must be positive".to_string(),
+        ));
+    }
+    let majority = &n / &m;
+    let quorum = parse(&rules.quorum, "quorum")?;
+    let dropped = debian_dropped(d, default, &majority, &quorum);
+
+    println!("Default option:");
+    println!("  {}", candidates[default]);
+    println!();
+    let mut order = (0..candidates.len())
+        .filter(|&c| dropped[c].is_some())
+        .collect::<Vec<_>>();
+    if !order.is_empty() {
+        order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+        println!("Dropped options:");
+        for c in order {
+            match dropped[c] {
+                Some(Dropped::Quorum) => println!(
+                    "  {}: preferred over the default option by {}, below the quorum of {}",
+                    candidates[c], d[c][default], quorum
+                ),
+                _ => println!(
+                    "  {}: preferred over the default option {} to {}, not more than {}:{}",
+                    candidates[c], d[c][default], d[default][c], n, m
+                ),
+            }
+        }
+        println!();
+    }
+
+    let kept = (0..candidates.len())
+        .filter(|&c| dropped[c].is_none())
+        .collect::<Vec<_>>();
+    let d1 = kept
+        .iter()
+        .map(|&a| {
+            kept.iter()
+                .map(|&b| d[a][b].clone())
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect::<Vec<_>>();
+    let mut ranking = condorcet_ranking(config.method, &d1, config.strength)
+        .iter()
+        .map(|group| singletons(&group.iter().map(|&i| kept[i]).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    if config.rank {
+        print_ranking(candidates, 1, &mut ranking);
+    } else {
+        print_winners(candidates, 1, &mut ranking[0]);
+    }
+    Ok(())
+}
+
+fn print_comparison(
+    candidates: &[String],
+    methods: &[(String, Method)],
+    rankings: &[Box<[Box<[usize]>]>],
+) {
+    let positions = rankings
+        .iter()
+        .map(|ranking| {
+            let mut position = vec![0; candidates.len()];
+            let mut p = 1;
+            for group in &**ranking {
+                for &c in &**group {
+                    position[c] = p;
+                }
+                p += group.len();
+            }
+            position
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    let rows = order
+        .iter()
+        .map(|&c| {
+            (
+                candidates[c].clone(),
+                positions.iter().map(|position| position[c].to_string()).collect(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let headers = methods
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    println!("Rank of each candidate:");
+    print_table(&headers, &rows);
+    println!();
+
+    println!("Winners:");
+    for ((name, _), ranking) in methods.iter().zip(rankings) {
+        let mut winners = ranking[0].to_vec();
+        winners.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+        println!("  {}: {}", name, format_set(candidates, &winners));
+    }
+}
+
+fn print_condorcet_summary<W>(candidates: &[String], d: &[Box<[W]>], strength: DefeatStrength)
+where
+    W: Display + Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let format_sorted = |set: &[usize]| {
+        let mut set = set.to_vec();
+        set.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+        format_set(candidates, &set)
+    };
+    let strength_name = STRENGTHS
+        .iter()
+        .find(|&&(_, s)| s == strength)
+        .unwrap()
+        .0;
+
+    println!("Condorcet summary:");
+    match condorcet_winner(d) {
+        Some(c) => println!("  Condorcet winner: {}", candidates[c]),
+        None => println!("  Condorcet winner: none"),
+    }
+    println!("  Smith set: {}", format_sorted(&smith_set(d)));
+    println!("  Schwartz set: {}", format_sorted(&schwartz_set(d)));
+    println!("  Copeland winners: {}", format_sorted(&copeland(d)));
+    println!(
+        "  Minimax winners ({}): {}",
+        strength_name,
+        format_sorted(&minimax(d, strength))
+    );
+    println!();
+
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    let copeland = copeland_scores(d);
+    let minimax = minimax_scores(d, strength);
+    let rows = order
+        .iter()
+        .map(|&c| {
+            (
+                candidates[c].clone(),
+                vec![copeland[c].to_string(), minimax[c].to_string()],
+            )
+        })
+        .collect::<Vec<_>>();
+    println!("Copeland scores (1 per win, 1/2 per tie) and Minimax strongest defeats:");
+    print_table(&["Copeland".to_string(), "Minimax".to_string()], &rows);
+    println!();
+}
+
+fn approval_ballots<W: Weight>(scores: &[(Scores<W>, W)]) -> Vec<(Vec<usize>, W)> {
+    scores
+        .iter()
+        .map(|(scores, w)| {
+            let approved = scores
+                .iter()
+                .filter(|(_, score)| *score != W::zero())
+                .map(|&(c, _)| c)
+                .collect::<Vec<_>>();
+            (approved, w.clone())
+        })
+        .collect()
+}
+
+fn print_committee_steps<W: Display>(
+    candidates: &[String],
+    steps: &[CommitteeStep<W>],
+    value: &str,
+    completion: &[usize],
+) {
+    println!("Elected in order:");
+    for (i, step) in steps.iter().enumerate() {
+        let mut notes = Vec::new();
+        if completion.contains(&step.candidate) {
+            notes.push(format!("completion, {} approvals", step.value));
+        } else {
+            notes.push(format!("{} {}", value, step.value));
+        }
+        if step.tied {
+            notes.push("tie broken".to_string());
+        }
+        println!(
+            "  {}. {} ({})",
+            i + 1,
+            candidates[step.candidate],
+            notes.join(", ")
+        );
+    }
+    println!();
+}
+
+fn print_totals<W: Display>(candidates: &[String], heading: &str, totals: &[W]) {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    let rows = order
+        .iter()
+        .map(|&c| (candidates[c].clone(), vec![totals[c].to_string()]))
+        .collect::<Vec<_>>();
+    print_table(&[heading.to_string()], &rows);
+    println!();
+}
+
+fn print_irv_rounds<W: Display + Weight>(candidates: &[String], rounds: &[IrvRound<W>]) {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+    let mut eliminated = vec![false; candidates.len()];
+    let mut rows = order
+        .iter()
+        .map(|&c| (candidates[c].clone(), Vec::new()))
+        .collect::<Vec<_>>();
+    let mut exhausted = Vec::new();
+    let mut tie_broken = false;
+    for round in rounds {
+        for (&c, row) in order.iter().zip(&mut rows) {
+            row.1.push(if eliminated[c] {
+                String::new()
+            } else {
+                round.votes[c].to_string()
+            });
+        }
+        exhausted.push(round.exhausted.to_string());
+        if let Some(c) = round.eliminated {
+            eliminated[c] = true;
+        }
+        tie_broken |= round.tied;
+    }
+    rows.push(("Exhausted".to_string(), exhausted));
+    rows.push((
+        "Eliminated".to_string(),
+        rounds
+            .iter()
+            .map(|round| match round.eliminated {
+                Some(c) if round.tied => format!("{}*", candidates[c]),
+                Some(c) => candidates[c].clone(),
+                None => String::new(),
+            })
+            .collect(),
+    ));
+    let headers = (1..rounds.len() + 1)
+        .map(|i| format!("Round {}", i))
+        .collect::<Vec<_>>();
+    print_table(&headers, &rows);
+    if tie_broken {
+        println!("  * tie broken by earlier rounds");
+    }
+    println!();
+}
+
+fn print_winners(candidates: &[String], num_seats: usize, winners: &mut [Box<[usize]>]) {
+    sort_sets(candidates, winners);
 
     let set_suffix = if num_seats == 1 { "" } else { " set" };
     if winners.len() == 1 {
@@ -162,20 +1819,61 @@ where
         println!("Tied winner{}s:", set_suffix);
     }
     for set in &*winners {
-        println!(
-            "  {}",
-            set.iter()
-                .map(|&c| &bp.candidates[c][..])
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
+        println!("  {}", format_set(candidates, set));
+    }
+}
+
+fn print_ranking(candidates: &[String], num_seats: usize, ranking: &mut [Box<[Box<[usize]>]>]) {
+    if num_seats == 1 {
+        println!("Ranking:");
+    } else {
+        println!("Ranking of winner sets:");
+    }
+    let mut position = 1;
+    for sets in ranking {
+        sort_sets(candidates, sets);
+        for set in &*sets {
+            println!("  {}. {}", position, format_set(candidates, set));
+        }
+        position += sets.len();
     }
-    Ok(())
 }
 
 fn main() {
-    main_result().unwrap_or_else(|e| {
-        writeln!(&mut stderr(), "{}", e).expect("failed printing to stderr");
+    let args = env::args().collect::<Vec<_>>();
+    main_result(&args).unwrap_or_else(|e| {
+        let message = match e {
+            // The reports name the files instead of the program.
+            CliError::Ballots(_) => e.to_string(),
+            _ => format!("{}: error: {}", args[0], e),
+        };
+        writeln!(&mut stderr(), "{}", message).expect("failed printing to stderr");
         exit(1)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use vote::hw_float::HwFloat;
+
+    use super::FORMATS;
+    use ballot_parser::{parse_ballot_files, BallotParser, BallotType};
+
+    #[test]
+    fn test_format_json() {
+        let format = FORMATS
+            .iter()
+            .find(|&&(name, _)| name == "json")
+            .map(|&(_, format)| format);
+        let path = env::temp_dir().join(format!("elect-test-{}", process::id()));
+        fs::write(&path, "{\"ballots\": [[\"Alice\", \"Bob\"]]}").unwrap();
+        let filenames = [path.to_str().unwrap()];
+        let bp: Result<BallotParser<HwFloat>, _> =
+            parse_ballot_files(&filenames, BallotType::Ranked, format, 0, false);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(bp.unwrap().candidates, ["Alice", "Bob"]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use traits::{Weight, WeightOps};
+
+pub struct StarResult<W> {
+    pub totals: Box<[W]>,
+    pub finalists: Box<[usize]>,
+    pub runoff: Box<[Box<[W]>]>,
+    pub winners: Box<[usize]>,
+}
+
+fn highest<W: Weight>(candidates: &[usize], totals: &[W]) -> Box<[usize]> {
+    match candidates.iter().map(|&c| &totals[c]).max() {
+        Some(max) => candidates
+            .iter()
+            .cloned()
+            .filter(|&c| totals[c].fuzzy_eq(max))
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+        None => Box::new([]),
+    }
+}
+
+pub fn approval_totals<W, Approved>(num_candidates: usize, ballots: &[(Approved, W)]) -> Box<[W]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let mut totals = vec![W::zero(); num_candidates].into_boxed_slice();
+    for (approved, w) in ballots {
+        for &c in approved.borrow() {
+            totals[c] = &totals[c] + w;
+        }
+    }
+    totals
+}
+
+pub fn approval<W, Approved>(num_candidates: usize, ballots: &[(Approved, W)]) -> Box<[usize]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let candidates = (0..num_candidates).collect::<Vec<_>>();
+    highest(&candidates, &approval_totals(num_candidates, ballots))
+}
+
+// Candidates missing from a score ballot are scored zero.
+pub fn score_totals<W, Scores>(num_candidates: usize, ballots: &[(Scores, W)]) -> Box<[W]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Scores: Borrow<[(usize, W)]>,
+{
+    let mut totals = vec![W::zero(); num_candidates].into_boxed_slice();
+    for (scores, w) in ballots {
+        for (c, score) in scores.borrow() {
+            totals[*c] = &totals[*c] + w * score;
+        }
+    }
+    totals
+}
+
+pub fn score<W, Scores>(num_candidates: usize, ballots: &[(Scores, W)]) -> Box<[usize]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Scores: Borrow<[(usize, W)]>,
+{
+    let candidates = (0..num_candidates).collect::<Vec<_>>();
+    highest(&candidates, &score_totals(num_candidates, ballots))
+}
+
+// d[a][b] is the weight of ballots scoring a higher than b.
+pub fn score_preferences<W, Scores>(
+    num_candidates: usize,
+    ballots: &[(Scores, W)],
+) -> Box<[Box<[W]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Scores: Borrow<[(usize, W)]>,
+{
+    let mut d = vec![vec![W::zero(); num_candidates].into_boxed_slice(); num_candidates];
+    let ballot_scores = &mut vec![W::zero(); num_candidates][..];
+    for (scores, w) in ballots {
+        for s in &mut *ballot_scores {
+            *s = W::zero();
+        }
+        for (c, score) in scores.borrow() {
+            ballot_scores[*c] = score.clone();
+        }
+        for (a, row) in d.iter_mut().enumerate() {
+            for (b, d_ab) in row.iter_mut().enumerate() {
+                if ballot_scores[a] > ballot_scores[b] {
+                    *d_ab = &*d_ab + w;
+                }
+            }
+        }
+    }
+    d.into_boxed_slice()
+}
+
+// Score Then Automatic Runoff.  The two highest scoring candidates are
+// finalists, or more if there is a tie for second place; the finalist
+// preferred over every other finalist wins, with ties in the runoff going to
+// the higher total score.  If the runoff is a cycle, the highest scoring
+// finalists win.
+pub fn star<W, Scores>(num_candidates: usize, ballots: &[(Scores, W)]) -> StarResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Scores: Borrow<[(usize, W)]>,
+{
+    let totals = score_totals(num_candidates, ballots);
+    let runoff = score_preferences(num_candidates, ballots);
+
+    let mut order = (0..num_candidates).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| totals[b].cmp(&totals[a]));
+    let finalists = match order.get(1) {
+        Some(&second) => order
+            .iter()
+            .cloned()
+            .filter(|&c| totals[c] > totals[second] || totals[c].fuzzy_eq(&totals[second]))
+            .collect::<Vec<_>>(),
+        None => order,
+    };
+
+    let unbeaten = finalists
+        .iter()
+        .cloned()
+        .filter(|&a| finalists.iter().all(|&b| runoff[a][b] >= runoff[b][a]))
+        .collect::<Vec<_>>();
+    let winners = if unbeaten.is_empty() {
+        highest(&finalists, &totals)
+    } else {
+        highest(&unbeaten, &totals)
+    };
+
+    StarResult {
+        totals,
+        finalists: finalists.into_boxed_slice(),
+        runoff,
+        winners,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use super::{approval, approval_totals, score, score_totals, star};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_approval() {
+        let ballots: &[(&[usize], Mpq)] = &[(&[0, 1], Q(3)), (&[1, 2], Q(2)), (&[], Q(1))];
+        assert_eq!(*approval_totals(3, ballots), [Q(3), Q(5), Q(2)]);
+        assert_eq!(*approval(3, ballots), [1]);
+        assert_eq!(*approval(3, &ballots[..1]), [0, 1]);
+    }
+
+    #[test]
+    fn test_score() {
+        let ballots: &[(&[(usize, Mpq)], Mpq)] = &[
+            (&[(0, Q(5)), (1, Q(3))], Q(2)),
+            (&[(1, Q(4)), (2, Q(5))], Q(1)),
+        ];
+        assert_eq!(*score_totals(3, ballots), [Q(10), Q(10), Q(5)]);
+        assert_eq!(*score(3, ballots), [0, 1]);
+    }
+
+    #[test]
+    fn test_star_1() {
+        // 1 has the highest total, but 0 is preferred in the runoff.
+        let ballots: &[(&[(usize, Mpq)], Mpq)] = &[
+            (&[(0, Q(5)), (1, Q(4))], Q(3)),
+            (&[(1, Q(5)), (2, Q(4))], Q(2)),
+            (&[(2, Q(5))], Q(1)),
+        ];
+        let result = star(3, ballots);
+        assert_eq!(*result.totals, [Q(15), Q(22), Q(13)]);
+        assert_eq!(*result.finalists, [1, 0]);
+        assert_eq!(result.runoff[0][1], Q(3));
+        assert_eq!(result.runoff[1][0], Q(2));
+        assert_eq!(*result.winners, [0]);
+    }
+
+    #[test]
+    fn test_star_2() {
+        // A tied runoff goes to the higher score.
+        let ballots: &[(&[(usize, Mpq)], Mpq)] = &[
+            (&[(0, Q(5)), (1, Q(1))], Q(1)),
+            (&[(0, Q(1)), (1, Q(2))], Q(1)),
+        ];
+        let result = star(2, ballots);
+        assert_eq!(*result.winners, [0]);
+    }
+
+    #[test]
+    fn test_star_cycle() {
+        // Three finalists with equal scores and a cyclic runoff all win.
+        let ballots: &[(&[(usize, Mpq)], Mpq)] = &[
+            (&[(0, Q(5)), (1, Q(4)), (2, Q(3))], Q(1)),
+            (&[(1, Q(5)), (2, Q(4)), (0, Q(3))], Q(1)),
+            (&[(2, Q(5)), (0, Q(4)), (1, Q(3))], Q(1)),
+        ];
+        let result = star(3, ballots);
+        assert_eq!(*result.finalists, [0, 1, 2]);
+        assert_eq!(result.runoff[0][1], Q(2));
+        assert_eq!(result.runoff[1][2], Q(2));
+        assert_eq!(result.runoff[2][0], Q(2));
+        assert_eq!(*result.winners, [0, 1, 2]);
+    }
+}
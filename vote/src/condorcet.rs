@@ -0,0 +1,182 @@
+use std::vec::Vec;
+
+use pairwise::DefeatStrength;
+use schwartz_set::schwartz_set as unbeaten_set;
+use traits::{Weight, WeightOps};
+
+fn defeaters<W, F>(d: &[Box<[W]>], defeats: F) -> Vec<Vec<usize>>
+where
+    F: Fn(&W, &W) -> bool,
+{
+    (0..d.len())
+        .map(|b| {
+            (0..d.len())
+                .filter(|&a| a != b && defeats(&d[a][b], &d[b][a]))
+                .collect()
+        })
+        .collect()
+}
+
+fn sorted(mut v: Box<[usize]>) -> Box<[usize]> {
+    v.sort();
+    v
+}
+
+fn best<W: Weight>(scores: &[W], highest: bool) -> Box<[usize]> {
+    let best = if highest {
+        scores.iter().max()
+    } else {
+        scores.iter().min()
+    };
+    match best {
+        Some(best) => (0..scores.len())
+            .filter(|&c| scores[c].fuzzy_eq(best))
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+        None => Box::new([]),
+    }
+}
+
+// The smallest set of candidates who each beat every candidate outside it:
+// the unbeaten set of the beats-or-ties relation.
+pub fn smith_set<W: Weight>(d: &[Box<[W]>]) -> Box<[usize]> {
+    let candidates = (0..d.len()).collect::<Vec<_>>();
+    sorted(unbeaten_set(&candidates, &defeaters(d, |d_ab, d_ba| d_ab >= d_ba)))
+}
+
+pub fn schwartz_set<W: Weight>(d: &[Box<[W]>]) -> Box<[usize]> {
+    let candidates = (0..d.len()).collect::<Vec<_>>();
+    sorted(unbeaten_set(&candidates, &defeaters(d, |d_ab, d_ba| d_ab > d_ba)))
+}
+
+pub fn condorcet_winner<W: Weight>(d: &[Box<[W]>]) -> Option<usize> {
+    let smith = smith_set(d);
+    if smith.len() == 1 {
+        Some(smith[0])
+    } else {
+        None
+    }
+}
+
+// One point for each pairwise win and half a point for each tie.
+pub fn copeland_scores<W>(d: &[Box<[W]>]) -> Box<[W]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let half = W::one() / W::from_i64(2);
+    (0..d.len())
+        .map(|a| {
+            (0..d.len())
+                .filter(|&b| b != a)
+                .fold(W::zero(), |acc, b| if d[a][b] > d[b][a] {
+                    acc + W::one()
+                } else if d[a][b] == d[b][a] {
+                    acc + &half
+                } else {
+                    acc
+                })
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+pub fn copeland<W>(d: &[Box<[W]>]) -> Box<[usize]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    best(&copeland_scores(d), true)
+}
+
+// The strength of the strongest pairwise defeat against each candidate, or
+// zero for a candidate who is never defeated.
+pub fn minimax_scores<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[W]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    (0..d.len())
+        .map(|b| {
+            (0..d.len())
+                .filter_map(|a| strength.strength(&d[a][b], &d[b][a]))
+                .max()
+                .unwrap_or_else(W::zero)
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+pub fn minimax<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[usize]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    best(&minimax_scores(d, strength), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use pairwise::DefeatStrength;
+    use super::{condorcet_winner, copeland, copeland_scores, minimax, minimax_scores,
+                schwartz_set, smith_set};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_condorcet_1() {
+        // Wikipedia
+        let d: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(20), Q(26), Q(30), Q(22)]),
+            Box::new([Q(25), Q(0), Q(16), Q(33), Q(18)]),
+            Box::new([Q(19), Q(29), Q(0), Q(17), Q(24)]),
+            Box::new([Q(15), Q(12), Q(28), Q(0), Q(14)]),
+            Box::new([Q(23), Q(27), Q(21), Q(31), Q(0)]),
+        ];
+        assert_eq!(*smith_set(d), [0, 1, 2, 3, 4]);
+        assert_eq!(*schwartz_set(d), [0, 1, 2, 3, 4]);
+        assert_eq!(condorcet_winner(d), None);
+        assert_eq!(*copeland_scores(d), [Q(2), Q(2), Q(2), Q(1), Q(3)]);
+        assert_eq!(*copeland(d), [4]);
+        assert_eq!(
+            *minimax_scores(d, DefeatStrength::WinningVotes),
+            [Q(25), Q(29), Q(28), Q(33), Q(24)]
+        );
+        assert_eq!(
+            *minimax_scores(d, DefeatStrength::Margins),
+            [Q(5), Q(13), Q(11), Q(21), Q(3)]
+        );
+        assert_eq!(*minimax(d, DefeatStrength::Margins), [4]);
+    }
+
+    #[test]
+    fn test_condorcet_2() {
+        // 0 and 1 tie, 1 beats 2, 2 and 0 tie: 2 is in the Smith set but
+        // not the Schwartz set.
+        let d: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(2), Q(2)]),
+            Box::new([Q(2), Q(0), Q(3)]),
+            Box::new([Q(2), Q(1), Q(0)]),
+        ];
+        assert_eq!(*smith_set(d), [0, 1, 2]);
+        assert_eq!(*schwartz_set(d), [0, 1]);
+        assert_eq!(condorcet_winner(d), None);
+        assert_eq!(*copeland_scores(d), [Q(1), Q(3) / Q(2), Q(1) / Q(2)]);
+        assert_eq!(*copeland(d), [1]);
+        assert_eq!(*minimax(d, DefeatStrength::WinningVotes), [0, 1]);
+    }
+
+    #[test]
+    fn test_condorcet_3() {
+        let d: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(3), Q(4)]),
+            Box::new([Q(2), Q(0), Q(1)]),
+            Box::new([Q(1), Q(4), Q(0)]),
+        ];
+        assert_eq!(*smith_set(d), [0]);
+        assert_eq!(condorcet_winner(d), Some(0));
+        assert_eq!(*minimax_scores(d, DefeatStrength::WinningVotes), [Q(0), Q(4), Q(4)]);
+    }
+}
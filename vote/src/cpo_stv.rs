@@ -0,0 +1,230 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use combination::{decode_combination, make_binomial};
+use meek::{distribute, MeekOptions};
+use ranked_pairs::{ranked_pairs, ranked_pairs_ranking};
+use schulze::{group_defeats, schulze_graph, schulze_ranking};
+use traits::{Weight, WeightOps};
+use util::{decimal_scale, round_up};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completion {
+    Schulze,
+    RankedPairs,
+}
+
+// Scores two outcomes against each other: candidates in neither outcome are
+// eliminated, and the surpluses of candidates in both are transferred Meek
+// style.  Candidates in both outcomes count equally towards either score.
+fn compare_outcomes<W, Group, Groups>(
+    num_candidates: usize,
+    x: &[usize],
+    y: &[usize],
+    ballots: &[(Groups, W)],
+    options: &MeekOptions<W>,
+) -> (W, W)
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let scale = options.precision.map(decimal_scale::<W>);
+    let total = ballots.iter().fold(W::zero(), |acc, (_, w)| acc + w);
+    let mut keep_factors = vec![W::zero(); num_candidates];
+    for &c in x.iter().chain(y) {
+        keep_factors[c] = W::one();
+    }
+    let common = x.iter()
+        .cloned()
+        .filter(|c| y.contains(c))
+        .collect::<Vec<_>>();
+
+    loop {
+        let (votes, exhausted) = distribute(num_candidates, ballots, &keep_factors);
+        let quota = options.quota.quota(&(&total - &exhausted), x.len());
+        let surplus = common
+            .iter()
+            .filter(|&&c| votes[c] > quota)
+            .fold(W::zero(), |acc, &c| acc + &votes[c] - &quota);
+
+        let mut changed = false;
+        if surplus > options.tolerance {
+            for &c in common.iter().filter(|&&c| votes[c] > quota) {
+                let mut keep_factor = &keep_factors[c] * &quota / &votes[c];
+                if let Some(ref scale) = scale {
+                    keep_factor = round_up(&keep_factor, scale);
+                }
+                if keep_factor != keep_factors[c] {
+                    keep_factors[c] = keep_factor;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            let score = |set: &[usize]| set.iter().fold(W::zero(), |acc, &c| acc + &votes[c]);
+            return (score(x), score(y));
+        }
+    }
+}
+
+fn defeat_groups<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+    options: &MeekOptions<W>,
+) -> Vec<Box<[(usize, usize)]>>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let binomial = make_binomial(num_candidates, num_seats);
+    let num_outcomes = binomial[num_candidates][num_seats];
+    let outcomes = (0..num_outcomes)
+        .map(|m| decode_combination(&binomial, num_seats, m))
+        .collect::<Vec<_>>();
+
+    let mut margins = Vec::new();
+    for m in 0..num_outcomes {
+        for m1 in m + 1..num_outcomes {
+            let (score, score1) =
+                compare_outcomes(num_candidates, &outcomes[m], &outcomes[m1], ballots, options);
+            if score.fuzzy_eq(&score1) {
+                continue;
+            } else if score > score1 {
+                margins.push((score - score1, (m, m1)));
+            } else {
+                margins.push((score1 - score, (m1, m)));
+            }
+        }
+    }
+
+    group_defeats(margins.iter().map(|(w, defeat)| (w, *defeat)).collect())
+}
+
+pub fn cpo_stv<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+    completion: Completion,
+    options: &MeekOptions<W>,
+) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let binomial = &make_binomial(num_candidates, num_seats);
+    let num_outcomes = binomial[num_candidates][num_seats];
+    let defeat_groups = defeat_groups(num_candidates, num_seats, ballots, options);
+
+    let winners = match completion {
+        Completion::Schulze => schulze_graph(num_outcomes, &defeat_groups),
+        Completion::RankedPairs => ranked_pairs(num_outcomes, &defeat_groups),
+    };
+    winners
+        .iter()
+        .map(|&m| decode_combination(binomial, num_seats, m))
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+pub fn cpo_stv_ranking<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+    completion: Completion,
+    options: &MeekOptions<W>,
+) -> Vec<Box<[Box<[usize]>]>>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let binomial = &make_binomial(num_candidates, num_seats);
+    let num_outcomes = binomial[num_candidates][num_seats];
+    let defeat_groups = defeat_groups(num_candidates, num_seats, ballots, options);
+
+    let ranking = match completion {
+        Completion::Schulze => schulze_ranking(num_outcomes, &defeat_groups),
+        Completion::RankedPairs => ranked_pairs_ranking(num_outcomes, &defeat_groups),
+    };
+    ranking
+        .iter()
+        .map(|sets| {
+            sets.iter()
+                .map(|&m| decode_combination(binomial, num_seats, m))
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use meek::MeekOptions;
+    use super::{compare_outcomes, cpo_stv, cpo_stv_ranking, Completion};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_compare_outcomes() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b]], Q(6)),
+            (&[&[b]], Q(3)),
+            (&[&[c]], Q(4)),
+        ];
+        let options = MeekOptions::default();
+        // No common candidate has a surplus.
+        assert_eq!(compare_outcomes(3, &[a, b], &[b, c], ballots, &options), (Q(9), Q(7)));
+        // a's surplus of 5/3 moves to b, and only counts once for a, c.
+        let (x, y) = compare_outcomes(3, &[a, b], &[a, c], ballots, &options);
+        assert_eq!(x, Q(9));
+        assert!(y < Q(17) / Q(2));
+    }
+
+    #[test]
+    fn test_cpo_stv_1() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b]], Q(6)),
+            (&[&[b]], Q(3)),
+            (&[&[c]], Q(4)),
+        ];
+        let options = MeekOptions::default();
+        for &completion in &[Completion::Schulze, Completion::RankedPairs] {
+            let expected: &[Box<[usize]>] = &[Box::new([a, b])];
+            assert_eq!(*cpo_stv(3, 2, ballots, completion, &options), *expected);
+            let expected: Vec<Box<[Box<[usize]>]>> = vec![
+                Box::new([Box::new([a, b])]),
+                Box::new([Box::new([a, c])]),
+                Box::new([Box::new([b, c])]),
+            ];
+            assert_eq!(cpo_stv_ranking(3, 2, ballots, completion, &options), expected);
+        }
+    }
+
+    #[test]
+    fn test_cpo_stv_2() {
+        // A Condorcet cycle among the outcomes, resolved by the weakest
+        // defeat.
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b], &[c]], Q(4)),
+            (&[&[b], &[c], &[a]], Q(3)),
+            (&[&[c], &[a], &[b]], Q(2)),
+        ];
+        let options = MeekOptions::default();
+        let expected: &[Box<[usize]>] = &[Box::new([a])];
+        assert_eq!(*cpo_stv(3, 1, ballots, Completion::Schulze, &options), *expected);
+        assert_eq!(*cpo_stv(3, 1, ballots, Completion::RankedPairs, &options), *expected);
+    }
+}
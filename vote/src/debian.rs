@@ -0,0 +1,68 @@
+use std::vec::Vec;
+
+use traits::{Weight, WeightOps};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dropped {
+    Quorum,
+    Majority,
+}
+
+// The Debian constitution drops an option before the Condorcet tally if
+// fewer than quorum voters prefer it over the default option, or if they do
+// not outnumber the voters preferring the default option by more than the
+// majority ratio (1 for a simple majority, 3 for a 3:1 supermajority).
+pub fn debian_dropped<W>(
+    d: &[Box<[W]>],
+    default: usize,
+    majority: &W,
+    quorum: &W,
+) -> Box<[Option<Dropped>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    (0..d.len())
+        .map(|a| if a == default {
+            None
+        } else if d[a][default] < *quorum {
+            Some(Dropped::Quorum)
+        } else if d[a][default] <= majority * &d[default][a] {
+            Some(Dropped::Majority)
+        } else {
+            None
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use super::{debian_dropped, Dropped};
+    use pairwise::pairwise_matrix;
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_debian_dropped() {
+        let (a, b, c, nota) = (0, 1, 2, 3);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b], &[nota], &[c]], Q(10)),
+            (&[&[b], &[nota], &[a], &[c]], Q(4)),
+            (&[&[nota], &[c], &[a], &[b]], Q(2)),
+        ];
+        let d = pairwise_matrix(4, ballots);
+
+        let dropped = debian_dropped(&d, nota, &Q(1), &Q(0));
+        assert_eq!(*dropped, [None, None, Some(Dropped::Majority), None]);
+
+        // a is preferred over the default 10:6, which fails 3:1.
+        let dropped = debian_dropped(&d, nota, &Q(3), &Q(0));
+        assert_eq!(*dropped, [Some(Dropped::Majority), None, Some(Dropped::Majority), None]);
+
+        let dropped = debian_dropped(&d, nota, &Q(1), &Q(12));
+        assert_eq!(*dropped, [Some(Dropped::Quorum), None, Some(Dropped::Quorum), None]);
+    }
+}
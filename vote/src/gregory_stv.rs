@@ -0,0 +1,483 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use quota::Quota;
+use traits::{Weight, WeightOps};
+use util::{decimal_scale, truncate};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GregoryRules {
+    // Scottish Local Government Elections Order 2007: integer Droop quota,
+    // transfer values truncated to 5 decimal places.
+    Scottish,
+    // Weighted Inclusive Gregory Method in exact arithmetic.
+    Wigm(Quota),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GregoryAction {
+    FirstPreferences,
+    Surplus(usize),
+    Exclusion(usize),
+}
+
+pub struct GregoryStage<W> {
+    pub action: GregoryAction,
+    pub tied: bool,
+    pub votes: Box<[W]>,
+    pub non_transferable: W,
+    pub lost: W,
+    pub elected: Box<[usize]>,
+}
+
+pub struct GregoryResult<W> {
+    pub quota: W,
+    pub elected: Box<[usize]>,
+    pub stages: Box<[GregoryStage<W>]>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Continuing,
+    Elected,
+    Excluded,
+}
+
+struct Paper<W> {
+    ballot: usize,
+    group: usize,
+    value: W,
+}
+
+struct Count<'a, W: 'a> {
+    ballots: Vec<(Vec<&'a [usize]>, &'a W)>,
+    states: Vec<State>,
+    papers: Vec<Vec<Paper<W>>>,
+    votes: Vec<W>,
+    non_transferable: W,
+    lost: W,
+}
+
+impl<'a, W> Count<'a, W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    fn allocate(&mut self, ballot: usize, start: usize, value: W) {
+        let w = self.ballots[ballot].1;
+        for group in start..self.ballots[ballot].0.len() {
+            let continuing = self.ballots[ballot].0[group]
+                .iter()
+                .cloned()
+                .filter(|&c| self.states[c] == State::Continuing)
+                .collect::<Vec<_>>();
+            if continuing.is_empty() {
+                continue;
+            }
+            let value = value / W::from_i64(continuing.len() as i64);
+            for c in continuing {
+                self.votes[c] = &self.votes[c] + w * &value;
+                self.papers[c].push(Paper {
+                    ballot,
+                    group,
+                    value: value.clone(),
+                });
+            }
+            return;
+        }
+        self.non_transferable = &self.non_transferable + w * value;
+    }
+
+    fn total(&self) -> W {
+        self.votes
+            .iter()
+            .fold(self.non_transferable.clone(), |acc, v| acc + v)
+    }
+
+    fn transfer<F>(&mut self, c: usize, transfer_value: F)
+    where
+        F: Fn(&W) -> W,
+    {
+        for paper in self.papers[c].split_off(0) {
+            let value = transfer_value(&paper.value);
+            self.allocate(paper.ballot, paper.group, value);
+        }
+    }
+
+    // Moves on the papers of an excluded candidate with the highest
+    // transfer value, and returns whether any are left.
+    fn transfer_highest(&mut self, c: usize) -> bool {
+        let max = self.papers[c]
+            .iter()
+            .map(|paper| &paper.value)
+            .max()
+            .cloned();
+        let (now, later): (Vec<_>, Vec<_>) = self.papers[c]
+            .drain(..)
+            .partition(|paper| Some(&paper.value) == max.as_ref());
+        self.papers[c] = later;
+        for paper in now {
+            let w = self.ballots[paper.ballot].1;
+            self.votes[c] = &self.votes[c] - w * &paper.value;
+            self.allocate(paper.ballot, paper.group, paper.value);
+        }
+        !self.papers[c].is_empty()
+    }
+}
+
+// Ties are broken by the votes at the earliest stage at which the tied
+// candidates had unequal votes (rules 49(2) and 50(2)), and otherwise by
+// candidate order in place of lots.
+fn break_tie<W: Weight>(
+    mut tied: Vec<usize>,
+    stages: &[GregoryStage<W>],
+    highest: bool,
+) -> usize {
+    for stage in stages {
+        if tied.len() == 1 {
+            break;
+        }
+        let votes = tied.iter().map(|&c| &stage.votes[c]);
+        let best = if highest { votes.max() } else { votes.min() }
+            .unwrap()
+            .clone();
+        tied.retain(|&c| stage.votes[c].fuzzy_eq(&best));
+    }
+    tied[0]
+}
+
+pub fn gregory_stv<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+    rules: GregoryRules,
+) -> GregoryResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let num_seats = num_seats.min(num_candidates);
+    if num_seats == 0 {
+        return GregoryResult {
+            quota: W::zero(),
+            elected: Box::new([]),
+            stages: Box::new([]),
+        };
+    }
+    let total = ballots.iter().fold(W::zero(), |acc, (_, w)| acc + w);
+    let (quota, scale) = match rules {
+        GregoryRules::Scottish => (
+            (total / W::from_i64(num_seats as i64 + 1)).floor() + W::one(),
+            Some(decimal_scale::<W>(5)),
+        ),
+        GregoryRules::Wigm(quota) => (quota.quota(&total, num_seats), None),
+    };
+    let reached = |votes: &W| match rules {
+        GregoryRules::Scottish => *votes >= quota,
+        GregoryRules::Wigm(q) => q.reached(votes, &quota),
+    };
+
+    let mut count = Count {
+        ballots: ballots
+            .iter()
+            .map(|(groups, w)| (groups.borrow().iter().map(|g| g.borrow()).collect(), w))
+            .collect(),
+        states: vec![State::Continuing; num_candidates],
+        papers: (0..num_candidates).map(|_| Vec::new()).collect(),
+        votes: vec![W::zero(); num_candidates],
+        non_transferable: W::zero(),
+        lost: W::zero(),
+    };
+    for ballot in 0..ballots.len() {
+        count.allocate(ballot, 0, W::one());
+    }
+
+    let mut elected = Vec::with_capacity(num_seats);
+    let mut pending = Vec::new();
+    let mut excluding = None;
+    let mut stages: Vec<GregoryStage<W>> = Vec::new();
+    let mut action = GregoryAction::FirstPreferences;
+    let mut tied = false;
+
+    loop {
+        let mut newly_elected = (0..num_candidates)
+            .filter(|&c| count.states[c] == State::Continuing && reached(&count.votes[c]))
+            .collect::<Vec<_>>();
+        newly_elected.sort_by(|&a, &b| count.votes[b].cmp(&count.votes[a]));
+        newly_elected.truncate(num_seats - elected.len());
+        let continuing = (0..num_candidates)
+            .filter(|&c| {
+                count.states[c] == State::Continuing && !newly_elected.contains(&c)
+            })
+            .collect::<Vec<_>>();
+        if elected.len() + newly_elected.len() + continuing.len() <= num_seats {
+            let mut rest = continuing.clone();
+            rest.sort_by(|&a, &b| count.votes[b].cmp(&count.votes[a]));
+            newly_elected.extend(rest);
+        }
+        for &c in &newly_elected {
+            count.states[c] = State::Elected;
+            elected.push(c);
+            pending.push(c);
+        }
+        stages.push(GregoryStage {
+            action,
+            tied,
+            votes: count.votes.clone().into_boxed_slice(),
+            non_transferable: count.non_transferable.clone(),
+            lost: count.lost.clone(),
+            elected: newly_elected.into_boxed_slice(),
+        });
+        if elected.len() == num_seats || continuing.is_empty() {
+            break;
+        }
+
+        pending.retain(|&c| count.votes[c] > quota);
+        if excluding.is_none() && !pending.is_empty() {
+            let max = pending.iter().map(|&c| &count.votes[c]).max().unwrap().clone();
+            let largest = pending
+                .iter()
+                .cloned()
+                .filter(|&c| count.votes[c].fuzzy_eq(&max))
+                .collect::<Vec<_>>();
+            tied = largest.len() > 1;
+            let c = break_tie(largest, &stages, true);
+            pending.retain(|&c1| c1 != c);
+
+            let surplus = &count.votes[c] - &quota;
+            let ratio = &surplus / &count.votes[c];
+            let before = count.total();
+            count.transfer(c, |value| {
+                let value = value * &ratio;
+                match scale {
+                    Some(ref scale) => truncate(&value, scale),
+                    None => value,
+                }
+            });
+            count.votes[c] = quota.clone();
+            count.lost = &count.lost + before - count.total();
+            action = GregoryAction::Surplus(c);
+        } else {
+            let c = match excluding {
+                Some(c) => {
+                    tied = false;
+                    c
+                }
+                None => {
+                    let min = continuing
+                        .iter()
+                        .map(|&c| &count.votes[c])
+                        .min()
+                        .unwrap()
+                        .clone();
+                    let lowest = continuing
+                        .iter()
+                        .cloned()
+                        .filter(|&c| count.votes[c].fuzzy_eq(&min))
+                        .collect::<Vec<_>>();
+                    tied = lowest.len() > 1;
+                    let c = break_tie(lowest, &stages, false);
+                    count.states[c] = State::Excluded;
+                    c
+                }
+            };
+            // The Scottish rules (rule 48) transfer the papers in sub-stages,
+            // highest transfer value first, so that candidates reaching the
+            // quota in one take no papers in the next.  WIGM moves them all
+            // at once.
+            let more = match rules {
+                GregoryRules::Scottish => count.transfer_highest(c),
+                GregoryRules::Wigm(_) => {
+                    count.transfer(c, |value| value.clone());
+                    false
+                }
+            };
+            if more {
+                excluding = Some(c);
+            } else {
+                count.votes[c] = W::zero();
+                excluding = None;
+            }
+            action = GregoryAction::Exclusion(c);
+        }
+    }
+
+    GregoryResult {
+        quota,
+        elected: elected.into_boxed_slice(),
+        stages: stages.into_boxed_slice(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use quota::Quota;
+    use super::{gregory_stv, GregoryAction, GregoryRules};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_scottish_stv_1() {
+        // The example election in Wikipedia's "Single transferable vote"
+        // article: 20 voters choosing 3 foods.
+        let (oranges, pears, chocolate, strawberries, sweets, hamburgers) = (0, 1, 2, 3, 4, 5);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[oranges]], Q(4)),
+            (&[&[pears], &[oranges]], Q(2)),
+            (&[&[chocolate], &[strawberries]], Q(8)),
+            (&[&[chocolate], &[sweets]], Q(4)),
+            (&[&[strawberries]], Q(1)),
+            (&[&[hamburgers]], Q(1)),
+        ];
+        let result = gregory_stv(6, 3, ballots, GregoryRules::Scottish);
+        assert_eq!(result.quota, Q(6));
+        assert_eq!(*result.elected, [chocolate, oranges, strawberries]);
+
+        let stages = &result.stages;
+        assert_eq!(stages.len(), 5);
+        assert_eq!(*stages[0].votes, [Q(4), Q(2), Q(12), Q(1), Q(0), Q(1)]);
+        assert_eq!(*stages[0].elected, [chocolate]);
+        assert_eq!(stages[1].action, GregoryAction::Surplus(chocolate));
+        assert_eq!(*stages[1].votes, [Q(4), Q(2), Q(6), Q(5), Q(2), Q(1)]);
+        assert_eq!(stages[2].action, GregoryAction::Exclusion(hamburgers));
+        // Pears and sweets tie; sweets had fewer first preferences.
+        assert_eq!(stages[3].action, GregoryAction::Exclusion(sweets));
+        assert!(stages[3].tied);
+        assert_eq!(stages[3].non_transferable, Q(3));
+        assert_eq!(stages[4].action, GregoryAction::Exclusion(pears));
+        assert_eq!(*stages[4].elected, [oranges, strawberries]);
+    }
+
+    #[test]
+    fn test_scottish_stv_sub_stages() {
+        // An excluded candidate's papers move in sub-stages, highest
+        // transfer value first (rule 48).
+        let (a, b, c, d) = (0, 1, 2, 3);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[c], &[b]], Q(44)),
+            (&[&[c], &[b]], Q(6)),
+            (&[&[b]], Q(25)),
+            (&[&[d]], Q(25)),
+        ];
+        let result = gregory_stv(4, 2, ballots, GregoryRules::Scottish);
+        assert_eq!(result.quota, Q(34));
+        assert_eq!(*result.elected, [a, b]);
+
+        let stages = &result.stages;
+        assert_eq!(stages.len(), 4);
+        // Transfer value 10/44 = 0.227272... is truncated to 0.22727.
+        assert_eq!(stages[1].action, GregoryAction::Surplus(a));
+        assert_eq!(
+            *stages[1].votes,
+            [Q(34), Q(25), Q(1599988) / Q(100000), Q(25)]
+        );
+        assert_eq!(stages[1].lost, Q(12) / Q(100000));
+        assert_eq!(stages[2].action, GregoryAction::Exclusion(c));
+        assert_eq!(
+            *stages[2].votes,
+            [Q(34), Q(31), Q(999988) / Q(100000), Q(25)]
+        );
+        assert!(stages[2].elected.is_empty());
+        assert_eq!(stages[3].action, GregoryAction::Exclusion(c));
+        assert_eq!(
+            *stages[3].votes,
+            [Q(34), Q(4099988) / Q(100000), Q(0), Q(25)]
+        );
+        assert_eq!(*stages[3].elected, [b]);
+    }
+
+    #[test]
+    fn test_scottish_stv_tie() {
+        // x and y tie for exclusion at stage 4; x was ahead at stage 1 but
+        // behind at stage 2, and the earliest stage decides.
+        let (w, x, y, p, q) = (0, 1, 2, 3, 4);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[w]], Q(10)),
+            (&[&[x]], Q(5)),
+            (&[&[y], &[w]], Q(4)),
+            (&[&[p], &[y], &[w]], Q(2)),
+            (&[&[q], &[x]], Q(1)),
+            (&[&[q]], Q(2)),
+        ];
+        let result = gregory_stv(5, 1, ballots, GregoryRules::Scottish);
+        assert_eq!(result.quota, Q(13));
+        assert_eq!(*result.elected, [w]);
+
+        let stages = &result.stages;
+        assert_eq!(stages.len(), 4);
+        assert_eq!(stages[1].action, GregoryAction::Exclusion(p));
+        assert_eq!(*stages[1].votes, [Q(10), Q(5), Q(6), Q(0), Q(3)]);
+        assert_eq!(stages[2].action, GregoryAction::Exclusion(q));
+        assert_eq!(*stages[2].votes, [Q(10), Q(6), Q(6), Q(0), Q(0)]);
+        assert_eq!(stages[3].action, GregoryAction::Exclusion(y));
+        assert!(stages[3].tied);
+        assert_eq!(*stages[3].elected, [w]);
+    }
+
+    #[test]
+    fn test_gregory_stv_few_candidates() {
+        let (a, b) = (0, 1);
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[a], &[b]], Q(3)), (&[&[b]], Q(1))];
+        let result = gregory_stv(2, 3, ballots, GregoryRules::Scottish);
+        assert_eq!(*result.elected, [a, b]);
+        let result = gregory_stv(2, 3, ballots, GregoryRules::Wigm(Quota::Hare));
+        assert_eq!(*result.elected, [a, b]);
+
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[], Q(1))];
+        let result = gregory_stv(0, 1, ballots, GregoryRules::Wigm(Quota::Hare));
+        assert!(result.elected.is_empty());
+        assert!(result.stages.is_empty());
+    }
+
+    #[test]
+    fn test_wigm_1() {
+        // The same election, with the exact quota of 5 to be exceeded.
+        let (oranges, pears, chocolate, strawberries, sweets, hamburgers) = (0, 1, 2, 3, 4, 5);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[oranges]], Q(4)),
+            (&[&[pears], &[oranges]], Q(2)),
+            (&[&[chocolate], &[strawberries]], Q(8)),
+            (&[&[chocolate], &[sweets]], Q(4)),
+            (&[&[strawberries]], Q(1)),
+            (&[&[hamburgers]], Q(1)),
+        ];
+        let result = gregory_stv(6, 3, ballots, GregoryRules::Wigm(Quota::Droop));
+        assert_eq!(result.quota, Q(5));
+        assert_eq!(*result.elected, [chocolate, strawberries, oranges]);
+
+        let stages = &result.stages;
+        assert_eq!(stages[1].action, GregoryAction::Surplus(chocolate));
+        assert_eq!(
+            *stages[1].votes,
+            [Q(4), Q(2), Q(5), Q(17) / Q(3), Q(7) / Q(3), Q(1)]
+        );
+        assert_eq!(*stages[1].elected, [strawberries]);
+        assert_eq!(stages[1].lost, Q(0));
+        assert_eq!(stages[2].action, GregoryAction::Surplus(strawberries));
+        assert_eq!(stages[2].non_transferable, Q(2) / Q(3));
+        let last = stages.last().unwrap();
+        assert_eq!(last.action, GregoryAction::Exclusion(pears));
+        assert_eq!(*last.elected, [oranges]);
+    }
+
+    #[test]
+    fn test_wigm_2() {
+        // Equal preferences are split, and a surplus elects the next
+        // candidate.
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a, b], &[c]], Q(6)),
+            (&[&[a], &[b]], Q(6)),
+            (&[&[c]], Q(3)),
+        ];
+        let result = gregory_stv(3, 2, ballots, GregoryRules::Wigm(Quota::Droop));
+        assert_eq!(result.quota, Q(5));
+        assert_eq!(*result.stages[0].votes, [Q(9), Q(3), Q(3)]);
+        assert_eq!(*result.elected, [a, b]);
+        assert_eq!(*result.stages[1].votes, [Q(5), Q(7), Q(3)]);
+    }
+}
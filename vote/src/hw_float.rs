@@ -115,6 +115,11 @@ impl Weight for HwFloat {
         HwFloat(n as f64)
     }
 
+    #[inline]
+    fn floor(&self) -> HwFloat {
+        HwFloat(self.0.floor())
+    }
+
     #[inline]
     fn fuzzy_eq(&self, other: &HwFloat) -> bool {
         (self.0 - other.0).abs() < 1.0e-8
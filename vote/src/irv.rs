@@ -0,0 +1,121 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use meek::distribute;
+use traits::{Weight, WeightOps};
+
+pub struct IrvRound<W> {
+    pub votes: Box<[W]>,
+    pub exhausted: W,
+    pub eliminated: Option<usize>,
+    pub tied: bool,
+}
+
+pub struct IrvResult<W> {
+    pub winners: Box<[usize]>,
+    pub rounds: Box<[IrvRound<W>]>,
+}
+
+pub fn irv<W, Group, Groups>(num_candidates: usize, ballots: &[(Groups, W)]) -> IrvResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let mut continuing = vec![W::one(); num_candidates];
+    let mut rounds: Vec<IrvRound<W>> = Vec::new();
+
+    loop {
+        let (votes, exhausted) = distribute(num_candidates, ballots, &continuing);
+        let remaining = (0..num_candidates)
+            .filter(|&c| !continuing[c].is_zero())
+            .collect::<Vec<_>>();
+        let active = remaining
+            .iter()
+            .fold(W::zero(), |acc, &c| acc + &votes[c]);
+        let max = remaining.iter().map(|&c| &votes[c]).max().unwrap().clone();
+        let min = remaining.iter().map(|&c| &votes[c]).min().unwrap().clone();
+
+        if &max * W::from_i64(2) > active || max.fuzzy_eq(&min) {
+            let winners = remaining
+                .into_iter()
+                .filter(|&c| votes[c].fuzzy_eq(&max))
+                .collect::<Vec<_>>();
+            rounds.push(IrvRound {
+                votes,
+                exhausted,
+                eliminated: None,
+                tied: false,
+            });
+            return IrvResult {
+                winners: winners.into_boxed_slice(),
+                rounds: rounds.into_boxed_slice(),
+            };
+        }
+
+        let mut lowest = remaining
+            .into_iter()
+            .filter(|&c| votes[c].fuzzy_eq(&min))
+            .collect::<Vec<_>>();
+        let tied = lowest.len() > 1;
+        for round in rounds.iter().rev() {
+            if lowest.len() == 1 {
+                break;
+            }
+            let min = lowest.iter().map(|&c| &round.votes[c]).min().unwrap().clone();
+            lowest.retain(|&c| round.votes[c].fuzzy_eq(&min));
+        }
+        let eliminated = lowest[0];
+        continuing[eliminated] = W::zero();
+        rounds.push(IrvRound {
+            votes,
+            exhausted,
+            eliminated: Some(eliminated),
+            tied,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use super::irv;
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_irv_1() {
+        // Wikipedia
+        let (m, n, c, k) = (0, 1, 2, 3);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[m], &[n], &[c], &[k]], Q(42)),
+            (&[&[n], &[c], &[k], &[m]], Q(26)),
+            (&[&[c], &[k], &[n], &[m]], Q(15)),
+            (&[&[k], &[c], &[n], &[m]], Q(17)),
+        ];
+        let result = irv(4, ballots);
+        assert_eq!(*result.winners, [k]);
+        assert_eq!(result.rounds.len(), 3);
+        assert_eq!(result.rounds[0].eliminated, Some(c));
+        assert_eq!(*result.rounds[1].votes, [Q(42), Q(26), Q(0), Q(32)]);
+        assert_eq!(result.rounds[1].eliminated, Some(n));
+        assert_eq!(*result.rounds[2].votes, [Q(42), Q(0), Q(0), Q(58)]);
+    }
+
+    #[test]
+    fn test_irv_2() {
+        // Equal preferences are split between the tied candidates.
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[0, 1]], Q(4)),
+            (&[&[1], &[2]], Q(1)),
+            (&[&[2]], Q(4)),
+        ];
+        let result = irv(3, ballots);
+        assert_eq!(*result.rounds[0].votes, [Q(2), Q(3), Q(4)]);
+        assert_eq!(result.rounds[0].eliminated, Some(0));
+        assert_eq!(*result.rounds[1].votes, [Q(0), Q(5), Q(4)]);
+        assert_eq!(*result.winners, [1]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use pairwise::pairwise_matrix;
+use traits::{Weight, WeightOps};
+
+pub struct KemenyResult<W> {
+    pub score: W,
+    pub rankings: Box<[Box<[usize]>]>,
+}
+
+fn collect_rankings(
+    last: &[Vec<usize>],
+    mask: usize,
+    tail: &mut Vec<usize>,
+    rankings: &mut Vec<Box<[usize]>>,
+) {
+    if mask == 0 {
+        rankings.push(tail.iter().rev().cloned().collect::<Vec<_>>().into_boxed_slice());
+        return;
+    }
+    for &c in &last[mask] {
+        tail.push(c);
+        collect_rankings(last, mask & !(1 << c), tail, rankings);
+        tail.pop();
+    }
+}
+
+// Dynamic programming over the set of candidates placed at the top of the
+// ranking, in O(2^n n^2) time.
+pub fn kemeny_young_matrix<W>(d: &[Box<[W]>]) -> KemenyResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let n = d.len();
+    let full = (1 << n) - 1;
+    let mut best: Vec<Option<W>> = vec![None; full + 1];
+    let mut last = vec![Vec::new(); full + 1];
+    best[0] = Some(W::zero());
+
+    for mask in 0..full {
+        let score = match best[mask] {
+            Some(ref score) => score.clone(),
+            None => continue,
+        };
+        for c in (0..n).filter(|&c| mask & (1 << c) == 0) {
+            let gain = (0..n)
+                .filter(|&b| b != c && mask & (1 << b) == 0)
+                .fold(W::zero(), |acc, b| acc + &d[c][b]);
+            let score1 = &score + gain;
+            let mask1 = mask | (1 << c);
+            let better = match best[mask1] {
+                Some(ref best1) if best1.fuzzy_eq(&score1) => {
+                    last[mask1].push(c);
+                    false
+                }
+                Some(ref best1) => score1 > *best1,
+                None => true,
+            };
+            if better {
+                best[mask1] = Some(score1);
+                last[mask1] = vec![c];
+            }
+        }
+    }
+
+    let mut rankings = Vec::new();
+    collect_rankings(&last, full, &mut Vec::with_capacity(n), &mut rankings);
+    rankings.sort();
+    KemenyResult {
+        score: best[full].take().unwrap(),
+        rankings: rankings.into_boxed_slice(),
+    }
+}
+
+pub fn kemeny_young<W, Group, Groups>(
+    num_candidates: usize,
+    ballots: &[(Groups, W)],
+) -> KemenyResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    kemeny_young_matrix(&pairwise_matrix(num_candidates, ballots))
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use super::kemeny_young;
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_kemeny_young_1() {
+        // Wikipedia
+        let (m, n, c, k) = (0, 1, 2, 3);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[m], &[n], &[c], &[k]], Q(42)),
+            (&[&[n], &[c], &[k], &[m]], Q(26)),
+            (&[&[c], &[k], &[n], &[m]], Q(15)),
+            (&[&[k], &[c], &[n], &[m]], Q(17)),
+        ];
+        let result = kemeny_young(4, ballots);
+        assert_eq!(result.score, Q(393));
+        let expected: &[Box<[usize]>] = &[Box::new([n, c, k, m])];
+        assert_eq!(*result.rankings, *expected);
+    }
+
+    #[test]
+    fn test_kemeny_young_2() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b], &[c]], Q(1)),
+            (&[&[b], &[c], &[a]], Q(1)),
+            (&[&[c], &[a], &[b]], Q(1)),
+        ];
+        let result = kemeny_young(3, ballots);
+        assert_eq!(result.score, Q(5));
+        let expected: &[Box<[usize]>] = &[
+            Box::new([a, b, c]),
+            Box::new([b, c, a]),
+            Box::new([c, a, b]),
+        ];
+        assert_eq!(*result.rankings, *expected);
+    }
+
+    #[test]
+    fn test_kemeny_young_3() {
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[0, 1]], Q(2))];
+        let result = kemeny_young(2, ballots);
+        assert_eq!(result.score, Q(0));
+        let expected: &[Box<[usize]>] = &[Box::new([0, 1]), Box::new([1, 0])];
+        assert_eq!(*result.rankings, *expected);
+    }
+}
@@ -6,13 +6,27 @@ extern crate num_integer;
 extern crate num_rational;
 extern crate num_traits;
 
+pub mod cardinal;
 mod combination;
+pub mod condorcet;
+pub mod cpo_stv;
+pub mod debian;
+pub mod gregory_stv;
 pub mod hw_float;
+pub mod irv;
+pub mod kemeny_young;
+pub mod meek;
+pub mod pairwise;
 pub mod traits;
+pub mod proportional_approval;
 mod proportional_completion;
+pub mod quota;
+pub mod ranked_pairs;
 pub mod schulze;
+pub mod schulze_proportional_ranking;
 pub mod schulze_stv;
 pub mod schwartz_set;
+pub mod split_cycle;
 mod util;
 mod vote_management;
 #[cfg(feature = "use-num-rational")]
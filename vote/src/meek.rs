@@ -0,0 +1,298 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use quota::Quota;
+use traits::{Weight, WeightOps};
+use util::{decimal_scale, round_up};
+
+pub struct MeekOptions<W> {
+    pub quota: Quota,
+    pub tolerance: W,
+    pub precision: Option<u32>,
+}
+
+impl<W: Weight> Default for MeekOptions<W> {
+    fn default() -> MeekOptions<W> {
+        MeekOptions {
+            quota: Quota::Droop,
+            tolerance: W::one() / W::from_i64(1_000_000),
+            precision: Some(9),
+        }
+    }
+}
+
+pub struct MeekRound<W> {
+    pub votes: Box<[W]>,
+    pub exhausted: W,
+    pub quota: W,
+    pub keep_factors: Box<[W]>,
+    pub elected: Box<[usize]>,
+    pub excluded: Option<usize>,
+    pub tied: bool,
+}
+
+pub struct MeekResult<W> {
+    pub elected: Box<[usize]>,
+    pub rounds: Box<[MeekRound<W>]>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Hopeful,
+    Elected,
+    Excluded,
+}
+
+pub fn distribute<W, Group, Groups>(
+    num_candidates: usize,
+    ballots: &[(Groups, W)],
+    keep_factors: &[W],
+) -> (Box<[W]>, W)
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let mut votes = vec![W::zero(); num_candidates].into_boxed_slice();
+    let mut exhausted = W::zero();
+    for (groups, w) in ballots {
+        let mut remaining = w.clone();
+        for group in groups.borrow() {
+            let group = group
+                .borrow()
+                .iter()
+                .filter(|&&c| !keep_factors[c].is_zero())
+                .collect::<Vec<_>>();
+            if group.is_empty() {
+                continue;
+            }
+            let share = &remaining / W::from_i64(group.len() as i64);
+            for &&c in &group {
+                let kept = &share * &keep_factors[c];
+                remaining = remaining - &kept;
+                votes[c] = &votes[c] + kept;
+            }
+            if remaining.is_zero() {
+                break;
+            }
+        }
+        exhausted = exhausted + remaining;
+    }
+    (votes, exhausted)
+}
+
+fn break_tie<W: Weight>(mut tied: Vec<usize>, rounds: &[MeekRound<W>]) -> usize {
+    for round in rounds.iter().rev() {
+        if tied.len() == 1 {
+            break;
+        }
+        let min = tied.iter().map(|&c| &round.votes[c]).min().unwrap().clone();
+        tied.retain(|&c| round.votes[c].fuzzy_eq(&min));
+    }
+    tied[0]
+}
+
+pub fn meek_stv<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+    options: &MeekOptions<W>,
+) -> MeekResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let num_seats = num_seats.min(num_candidates);
+    let scale = options.precision.map(decimal_scale::<W>);
+    let total = ballots.iter().fold(W::zero(), |acc, (_, w)| acc + w);
+    let mut states = vec![State::Hopeful; num_candidates];
+    let mut keep_factors = vec![W::one(); num_candidates];
+    let mut elected = Vec::with_capacity(num_seats);
+    let mut rounds = Vec::new();
+    if num_seats == 0 {
+        return MeekResult {
+            elected: elected.into_boxed_slice(),
+            rounds: rounds.into_boxed_slice(),
+        };
+    }
+
+    loop {
+        let mut newly_elected = Vec::new();
+        let (votes, exhausted, quota) = loop {
+            let (votes, exhausted) = distribute(num_candidates, ballots, &keep_factors);
+            let quota = options.quota.quota(&(&total - &exhausted), num_seats);
+
+            let mut winners = (0..num_candidates)
+                .filter(|&c| {
+                    states[c] == State::Hopeful && options.quota.reached(&votes[c], &quota)
+                })
+                .collect::<Vec<_>>();
+            winners.sort_by(|&a, &b| votes[b].cmp(&votes[a]));
+            winners.truncate(num_seats - elected.len());
+            for &c in &winners {
+                states[c] = State::Elected;
+                elected.push(c);
+                newly_elected.push(c);
+            }
+            if elected.len() == num_seats {
+                break (votes, exhausted, quota);
+            }
+
+            let surplus = elected
+                .iter()
+                .filter(|&&c| votes[c] > quota)
+                .fold(W::zero(), |acc, &c| acc + &votes[c] - &quota);
+            if surplus <= options.tolerance {
+                break (votes, exhausted, quota);
+            }
+
+            let mut changed = false;
+            for &c in elected.iter().filter(|&&c| votes[c] > quota) {
+                let mut keep_factor = &keep_factors[c] * &quota / &votes[c];
+                if let Some(ref scale) = scale {
+                    keep_factor = round_up(&keep_factor, scale);
+                }
+                if keep_factor != keep_factors[c] {
+                    keep_factors[c] = keep_factor;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break (votes, exhausted, quota);
+            }
+        };
+
+        let mut hopeful = (0..num_candidates)
+            .filter(|&c| states[c] == State::Hopeful)
+            .collect::<Vec<_>>();
+        if elected.len() + hopeful.len() <= num_seats {
+            hopeful.sort_by(|&a, &b| votes[b].cmp(&votes[a]));
+            for &c in &hopeful {
+                states[c] = State::Elected;
+            }
+            elected.extend_from_slice(&hopeful);
+            newly_elected.extend_from_slice(&hopeful);
+            hopeful.clear();
+        }
+        if elected.len() == num_seats || hopeful.is_empty() {
+            rounds.push(MeekRound {
+                votes,
+                exhausted,
+                quota,
+                keep_factors: keep_factors.clone().into_boxed_slice(),
+                elected: newly_elected.into_boxed_slice(),
+                excluded: None,
+                tied: false,
+            });
+            break;
+        }
+
+        let min = hopeful.iter().map(|&c| &votes[c]).min().unwrap().clone();
+        let lowest = hopeful
+            .into_iter()
+            .filter(|&c| votes[c].fuzzy_eq(&min))
+            .collect::<Vec<_>>();
+        let tied = lowest.len() > 1;
+        let excluded = break_tie(lowest, &rounds);
+        states[excluded] = State::Excluded;
+        rounds.push(MeekRound {
+            votes,
+            exhausted,
+            quota,
+            keep_factors: keep_factors.clone().into_boxed_slice(),
+            elected: newly_elected.into_boxed_slice(),
+            excluded: Some(excluded),
+            tied,
+        });
+        keep_factors[excluded] = W::zero();
+    }
+
+    MeekResult {
+        elected: elected.into_boxed_slice(),
+        rounds: rounds.into_boxed_slice(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use hw_float::HwFloat;
+    use quota::Quota;
+    use traits::Weight;
+    use super::{distribute, meek_stv, MeekOptions};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_distribute() {
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[0, 1], &[2]], Q(4)), (&[&[1]], Q(2))];
+        let (votes, exhausted) = distribute(3, ballots, &[Q(1), Q(1) / Q(2), Q(0)]);
+        assert_eq!(*votes, [Q(2), Q(2), Q(0)]);
+        assert_eq!(exhausted, Q(2));
+    }
+
+    #[test]
+    fn test_meek_stv_1() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[b]], Q(8)),
+            (&[&[b]], Q(2)),
+            (&[&[c]], Q(4)),
+        ];
+        let result = meek_stv(3, 2, ballots, &MeekOptions::default());
+        assert_eq!(*result.elected, [a, b]);
+        assert_eq!(result.rounds.len(), 1);
+        assert_eq!(result.rounds[0].quota, Q(14) / Q(3));
+        assert_eq!(result.rounds[0].keep_factors[a], Q(583333334) / Q(1000000000));
+    }
+
+    #[test]
+    fn test_meek_stv_2() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a]], Q(4)),
+            (&[&[b], &[a]], Q(3)),
+            (&[&[c], &[b]], Q(2)),
+        ];
+        let result = meek_stv(3, 1, ballots, &MeekOptions::default());
+        assert_eq!(*result.elected, [b]);
+        assert_eq!(result.rounds[0].excluded, Some(c));
+        assert_eq!(*result.rounds[1].votes, [Q(4), Q(5), Q(0)]);
+
+        let options = MeekOptions {
+            quota: Quota::Hare,
+            ..MeekOptions::default()
+        };
+        let result = meek_stv(3, 2, ballots, &options);
+        assert_eq!(*result.elected, [b, a]);
+    }
+
+    #[test]
+    fn test_meek_stv_few_candidates() {
+        let (a, b) = (0, 1);
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[a], &[b]], Q(3)), (&[&[b]], Q(1))];
+        let result = meek_stv(2, 5, ballots, &MeekOptions::default());
+        assert_eq!(*result.elected, [a, b]);
+
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[], Q(1))];
+        let result = meek_stv(0, 1, ballots, &MeekOptions::default());
+        assert!(result.elected.is_empty());
+        assert!(result.rounds.is_empty());
+    }
+
+    #[test]
+    fn test_meek_stv_hw_float() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[&[usize]], HwFloat)] = &[
+            (&[&[a], &[b]], HwFloat::from_i64(8)),
+            (&[&[b]], HwFloat::from_i64(2)),
+            (&[&[c]], HwFloat::from_i64(4)),
+        ];
+        let result = meek_stv(3, 2, ballots, &MeekOptions::default());
+        assert_eq!(*result.elected, [a, b]);
+    }
+}
@@ -0,0 +1,172 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use schulze::group_defeats;
+use traits::{Weight, WeightOps};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DefeatStrength {
+    WinningVotes,
+    Margins,
+    Ratio,
+}
+
+impl DefeatStrength {
+    pub fn strength<W>(self, d_ab: &W, d_ba: &W) -> Option<W>
+    where
+        W: Weight,
+        for<'w> &'w W: WeightOps<W>,
+    {
+        if d_ab <= d_ba {
+            return None;
+        }
+        Some(match self {
+            DefeatStrength::WinningVotes => d_ab.clone(),
+            DefeatStrength::Margins => d_ab - d_ba,
+            // Monotone in d_ab / d_ba, but finite when d_ba is zero.
+            DefeatStrength::Ratio => d_ab / (d_ab + d_ba),
+        })
+    }
+}
+
+pub fn pairwise_matrix<W, Group, Groups>(
+    num_candidates: usize,
+    ballots: &[(Groups, W)],
+) -> Box<[Box<[W]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let mut d = vec![vec![W::zero(); num_candidates].into_boxed_slice(); num_candidates];
+    let rank = &mut vec![0; num_candidates][..];
+    for (groups, w) in ballots {
+        let groups = groups.borrow();
+        for r in &mut *rank {
+            *r = groups.len();
+        }
+        for (i, group) in groups.iter().enumerate() {
+            for &c in group.borrow() {
+                rank[c] = i;
+            }
+        }
+        for (a, row) in d.iter_mut().enumerate() {
+            for (b, d_ab) in row.iter_mut().enumerate() {
+                if rank[a] < rank[b] {
+                    *d_ab = &*d_ab + w;
+                }
+            }
+        }
+    }
+    d.into_boxed_slice()
+}
+
+pub fn defeat_strengths<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[Box<[W]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    (0..d.len())
+        .map(|a| {
+            (0..d.len())
+                .map(|b| {
+                    strength
+                        .strength(&d[a][b], &d[b][a])
+                        .unwrap_or_else(W::zero)
+                })
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+pub fn pairwise_defeat_groups<W>(
+    d: &[Box<[W]>],
+    strength: DefeatStrength,
+) -> Vec<Box<[(usize, usize)]>>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let strengths = defeat_strengths(d, strength);
+    let defeats = (0..d.len())
+        .flat_map(|a| (0..d.len()).map(move |b| (a, b)))
+        .filter(|&(a, b)| d[a][b] > d[b][a])
+        .map(|(a, b)| (&strengths[a][b], (a, b)))
+        .collect::<Vec<_>>();
+    group_defeats(defeats)
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use schulze::{schulze_graph, schulze_ranking};
+    use super::{pairwise_defeat_groups, pairwise_matrix, DefeatStrength};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_pairwise_matrix_1() {
+        // Wikipedia
+        let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[c], &[b], &[e], &[d]], Q(5)),
+            (&[&[a], &[d], &[e], &[c], &[b]], Q(5)),
+            (&[&[b], &[e], &[d], &[a], &[c]], Q(8)),
+            (&[&[c], &[a], &[b], &[e], &[d]], Q(3)),
+            (&[&[c], &[a], &[e], &[b], &[d]], Q(7)),
+            (&[&[c], &[b], &[a], &[d], &[e]], Q(2)),
+            (&[&[d], &[c], &[e], &[b], &[a]], Q(7)),
+            (&[&[e], &[b], &[a], &[d], &[c]], Q(8)),
+        ];
+        let expected: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(20), Q(26), Q(30), Q(22)]),
+            Box::new([Q(25), Q(0), Q(16), Q(33), Q(18)]),
+            Box::new([Q(19), Q(29), Q(0), Q(17), Q(24)]),
+            Box::new([Q(15), Q(12), Q(28), Q(0), Q(14)]),
+            Box::new([Q(23), Q(27), Q(21), Q(31), Q(0)]),
+        ];
+        assert_eq!(*pairwise_matrix(5, ballots), *expected);
+    }
+
+    #[test]
+    fn test_pairwise_matrix_2() {
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[0, 1], &[2]], Q(2)), (&[&[2]], Q(3))];
+        let expected: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(0), Q(2)]),
+            Box::new([Q(0), Q(0), Q(2)]),
+            Box::new([Q(3), Q(3), Q(0)]),
+        ];
+        assert_eq!(*pairwise_matrix(3, ballots), *expected);
+    }
+
+    #[test]
+    fn test_defeat_strength() {
+        assert_eq!(DefeatStrength::WinningVotes.strength(&Q(5), &Q(3)), Some(Q(5)));
+        assert_eq!(DefeatStrength::Margins.strength(&Q(5), &Q(3)), Some(Q(2)));
+        assert_eq!(DefeatStrength::Ratio.strength(&Q(5), &Q(3)), Some(Q(5) / Q(8)));
+        assert_eq!(DefeatStrength::Ratio.strength(&Q(5), &Q(0)), Some(Q(1)));
+        assert_eq!(DefeatStrength::WinningVotes.strength(&Q(3), &Q(5)), None);
+        assert_eq!(DefeatStrength::Margins.strength(&Q(4), &Q(4)), None);
+    }
+
+    #[test]
+    fn test_pairwise_defeat_groups_1() {
+        let d: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(10), Q(8)]),
+            Box::new([Q(5), Q(0), Q(9)]),
+            Box::new([Q(11), Q(1), Q(0)]),
+        ];
+        let groups = pairwise_defeat_groups(d, DefeatStrength::WinningVotes);
+        assert_eq!(*schulze_graph(3, &groups), [2]);
+        let groups = pairwise_defeat_groups(d, DefeatStrength::Ratio);
+        assert_eq!(*schulze_graph(3, &groups), [0]);
+        let groups = pairwise_defeat_groups(d, DefeatStrength::Margins);
+        assert_eq!(*schulze_graph(3, &groups), [0]);
+        let expected: &[Box<[usize]>] = &[Box::new([0]), Box::new([1]), Box::new([2])];
+        assert_eq!(*schulze_ranking(3, &groups), *expected);
+    }
+}
@@ -0,0 +1,338 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use combination::{decode_combination, make_binomial};
+use traits::{Weight, WeightOps};
+
+pub struct CommitteeStep<W> {
+    pub candidate: usize,
+    pub value: W,
+    pub tied: bool,
+}
+
+pub struct EqualSharesResult<W> {
+    pub steps: Box<[CommitteeStep<W>]>,
+    // Candidates elected by the completion after no candidate was
+    // affordable any more.
+    pub completion: Box<[usize]>,
+}
+
+fn approves<Approved: Borrow<[usize]>>(approved: &Approved, c: usize) -> bool {
+    approved.borrow().contains(&c)
+}
+
+// Picks the candidate with the lowest value (or highest, if highest is set),
+// preferring the lowest index among tied candidates.
+fn pick<W: Weight>(values: Vec<(usize, W)>, highest: bool) -> Option<CommitteeStep<W>> {
+    let best = if highest {
+        values.iter().map(|(_, v)| v).max()
+    } else {
+        values.iter().map(|(_, v)| v).min()
+    }?.clone();
+    let mut tied = values.into_iter().filter(|(_, v)| v.fuzzy_eq(&best));
+    let (candidate, value) = tied.next().unwrap();
+    Some(CommitteeStep {
+        candidate,
+        value,
+        tied: tied.next().is_some(),
+    })
+}
+
+fn harmonic<W>(k: usize) -> W
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    (1..k + 1).fold(W::zero(), |acc, i| acc + W::one() / W::from_i64(i as i64))
+}
+
+pub fn pav_score<W, Approved>(committee: &[usize], ballots: &[(Approved, W)]) -> W
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    ballots.iter().fold(W::zero(), |acc, (approved, w)| {
+        let k = committee.iter().filter(|&&c| approves(approved, c)).count();
+        acc + w * harmonic::<W>(k)
+    })
+}
+
+// Enumerates every committee, so only suitable for small elections.
+pub fn pav<W, Approved>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Approved, W)],
+) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let num_seats = num_seats.min(num_candidates);
+    let binomial = make_binomial(num_candidates, num_seats);
+    let committees = (0..binomial[num_candidates][num_seats])
+        .map(|m| {
+            let committee = decode_combination(&binomial, num_seats, m);
+            let score = pav_score(&committee, ballots);
+            (committee, score)
+        })
+        .collect::<Vec<_>>();
+    let best = match committees.iter().map(|(_, score)| score).max() {
+        Some(best) => best.clone(),
+        None => return Box::new([]),
+    };
+    committees
+        .into_iter()
+        .filter(|(_, score)| score.fuzzy_eq(&best))
+        .map(|(committee, _)| committee)
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+// Each step elects the candidate adding the most to the PAV score.
+pub fn sequential_pav<W, Approved>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Approved, W)],
+) -> Box<[CommitteeStep<W>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let mut elected = vec![false; num_candidates];
+    let mut represented = vec![0; ballots.len()];
+    let mut steps = Vec::with_capacity(num_seats);
+    while steps.len() < num_seats.min(num_candidates) {
+        let gains = (0..num_candidates)
+            .filter(|&c| !elected[c])
+            .map(|c| {
+                let gain = ballots
+                    .iter()
+                    .zip(&represented)
+                    .filter(|&((approved, _), _)| approves(approved, c))
+                    .fold(W::zero(), |acc, ((_, w), &k)| {
+                        acc + w / W::from_i64(k as i64 + 1)
+                    });
+                (c, gain)
+            })
+            .collect();
+        let step = pick(gains, true).unwrap();
+        elected[step.candidate] = true;
+        for ((approved, _), k) in ballots.iter().zip(&mut represented) {
+            if approves(approved, step.candidate) {
+                *k += 1;
+            }
+        }
+        steps.push(step);
+    }
+    steps.into_boxed_slice()
+}
+
+// Each step elects the candidate whose cost of one seat, shared out so that
+// its voters end up with equal loads, gives the lowest maximum load.
+// Candidates nobody approves are only elected once no other candidates are
+// left, with a value of zero.
+pub fn phragmen<W, Approved>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Approved, W)],
+) -> Box<[CommitteeStep<W>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let mut elected = vec![false; num_candidates];
+    let mut loads = vec![W::zero(); ballots.len()];
+    let mut steps = Vec::with_capacity(num_seats);
+    while steps.len() < num_seats.min(num_candidates) {
+        let new_loads = (0..num_candidates)
+            .filter(|&c| !elected[c])
+            .filter_map(|c| {
+                let (support, load) = ballots
+                    .iter()
+                    .zip(&loads)
+                    .filter(|&((approved, _), _)| approves(approved, c))
+                    .fold((W::zero(), W::one()), |(support, load), ((_, w), l)| {
+                        (support + w, load + w * l)
+                    });
+                if support.is_zero() {
+                    None
+                } else {
+                    Some((c, load / support))
+                }
+            })
+            .collect();
+        let step = pick(new_loads, false).unwrap_or_else(|| {
+            let unapproved = (0..num_candidates)
+                .filter(|&c| !elected[c])
+                .map(|c| (c, W::zero()))
+                .collect();
+            pick(unapproved, false).unwrap()
+        });
+        elected[step.candidate] = true;
+        for ((approved, _), load) in ballots.iter().zip(&mut loads) {
+            if approves(approved, step.candidate) {
+                *load = step.value.clone();
+            }
+        }
+        steps.push(step);
+    }
+    steps.into_boxed_slice()
+}
+
+// Each ballot starts with an equal share of the seats per unit of weight.
+// Each step elects the affordable candidate whose cost of one seat can be
+// paid with the lowest maximum payment rho per unit of weight.  Seats left
+// over once no candidate is affordable go to the most approved candidates.
+pub fn equal_shares<W, Approved>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Approved, W)],
+) -> EqualSharesResult<W>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Approved: Borrow<[usize]>,
+{
+    let total = ballots.iter().fold(W::zero(), |acc, (_, w)| acc + w);
+    let share = W::from_i64(num_seats as i64) / total;
+    let mut budgets = vec![share; ballots.len()];
+    let mut elected = vec![false; num_candidates];
+    let mut steps = Vec::with_capacity(num_seats);
+
+    while steps.len() < num_seats.min(num_candidates) {
+        let prices = (0..num_candidates)
+            .filter(|&c| !elected[c])
+            .filter_map(|c| {
+                let mut supporters = ballots
+                    .iter()
+                    .zip(&budgets)
+                    .filter(|&((approved, _), _)| approves(approved, c))
+                    .map(|((_, w), b)| (b, w))
+                    .collect::<Vec<_>>();
+                supporters.sort_by(|a, b| a.0.cmp(b.0));
+                let mut cost = W::one();
+                let mut weight = supporters.iter().fold(W::zero(), |acc, (_, w)| acc + *w);
+                for (b, w) in supporters {
+                    let rho = &cost / &weight;
+                    if rho <= *b {
+                        return Some((c, rho));
+                    }
+                    cost = cost - w * b;
+                    weight = weight - w;
+                }
+                None
+            })
+            .collect();
+        let step = match pick(prices, false) {
+            Some(step) => step,
+            None => break,
+        };
+        elected[step.candidate] = true;
+        for ((approved, _), b) in ballots.iter().zip(&mut budgets) {
+            if approves(approved, step.candidate) {
+                *b = if *b > step.value {
+                    &*b - &step.value
+                } else {
+                    W::zero()
+                };
+            }
+        }
+        steps.push(step);
+    }
+
+    let mut completion = Vec::new();
+    while steps.len() < num_seats.min(num_candidates) {
+        let totals = (0..num_candidates)
+            .filter(|&c| !elected[c])
+            .map(|c| {
+                let total = ballots
+                    .iter()
+                    .filter(|(approved, _)| approves(approved, c))
+                    .fold(W::zero(), |acc, (_, w)| acc + w);
+                (c, total)
+            })
+            .collect();
+        let step = pick(totals, true).unwrap();
+        elected[step.candidate] = true;
+        completion.push(step.candidate);
+        steps.push(step);
+    }
+
+    EqualSharesResult {
+        steps: steps.into_boxed_slice(),
+        completion: completion.into_boxed_slice(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use super::{equal_shares, pav, pav_score, phragmen, sequential_pav};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_pav() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[usize], Mpq)] = &[(&[a, b], Q(3)), (&[c], Q(2))];
+        assert_eq!(pav_score(&[a, b], ballots), Q(9) / Q(2));
+        assert_eq!(pav_score(&[a, c], ballots), Q(5));
+        let expected: &[Box<[usize]>] = &[Box::new([a, c]), Box::new([b, c])];
+        assert_eq!(*pav(3, 2, ballots), *expected);
+
+        // With seats to spare, every candidate is elected.
+        let expected: &[Box<[usize]>] = &[Box::new([a, b, c])];
+        assert_eq!(*pav(3, 4, ballots), *expected);
+    }
+
+    #[test]
+    fn test_sequential_pav() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[usize], Mpq)] = &[(&[a, b], Q(3)), (&[c], Q(2))];
+        let steps = sequential_pav(3, 2, ballots);
+        assert_eq!(steps.iter().map(|s| s.candidate).collect::<Vec<_>>(), [a, c]);
+        assert_eq!(steps[0].value, Q(3));
+        assert!(steps[0].tied);
+        assert_eq!(steps[1].value, Q(2));
+        assert!(!steps[1].tied);
+    }
+
+    #[test]
+    fn test_phragmen() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[usize], Mpq)] = &[(&[a, b], Q(3)), (&[c], Q(2))];
+        let steps = phragmen(3, 3, ballots);
+        assert_eq!(steps.iter().map(|s| s.candidate).collect::<Vec<_>>(), [a, c, b]);
+        assert_eq!(steps[0].value, Q(1) / Q(3));
+        assert_eq!(steps[1].value, Q(1) / Q(2));
+        assert_eq!(steps[2].value, Q(2) / Q(3));
+    }
+
+    #[test]
+    fn test_equal_shares_1() {
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[usize], Mpq)] = &[(&[a, b], Q(3)), (&[c], Q(3))];
+        let result = equal_shares(3, 2, ballots);
+        let elected = result.steps.iter().map(|s| s.candidate).collect::<Vec<_>>();
+        assert_eq!(elected, [a, c]);
+        assert_eq!(result.steps[0].value, Q(1) / Q(3));
+        assert!(result.completion.is_empty());
+    }
+
+    #[test]
+    fn test_equal_shares_2() {
+        // c's voters can only afford 4/5 of a seat, so the second seat is
+        // filled by the completion.
+        let (a, b, c) = (0, 1, 2);
+        let ballots: &[(&[usize], Mpq)] = &[(&[a, b], Q(3)), (&[c], Q(2))];
+        let result = equal_shares(3, 2, ballots);
+        let elected = result.steps.iter().map(|s| s.candidate).collect::<Vec<_>>();
+        assert_eq!(elected, [a, b]);
+        assert_eq!(*result.completion, [b]);
+    }
+}
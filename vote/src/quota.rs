@@ -0,0 +1,29 @@
+use traits::{Weight, WeightOps};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quota {
+    Droop,
+    Hare,
+}
+
+impl Quota {
+    pub fn quota<W>(self, total: &W, num_seats: usize) -> W
+    where
+        W: Weight,
+        for<'w> &'w W: WeightOps<W>,
+    {
+        match self {
+            Quota::Droop => total / W::from_i64(num_seats as i64 + 1),
+            Quota::Hare => total / W::from_i64(num_seats as i64),
+        }
+    }
+
+    // An exact Droop quota can be reached by one more candidate than there
+    // are seats, so it must be exceeded.
+    pub fn reached<W: Weight>(self, votes: &W, quota: &W) -> bool {
+        match self {
+            Quota::Droop => votes > quota,
+            Quota::Hare => votes >= quota,
+        }
+    }
+}
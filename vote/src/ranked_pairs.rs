@@ -0,0 +1,153 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+fn reaches(beats: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut seen = vec![false; beats.len()];
+    let mut stack = vec![from];
+    seen[from] = true;
+    while let Some(c) = stack.pop() {
+        if c == to {
+            return true;
+        }
+        for &c1 in &beats[c] {
+            if !seen[c1] {
+                seen[c1] = true;
+                stack.push(c1);
+            }
+        }
+    }
+    false
+}
+
+pub fn ranked_pairs_locked<DefeatGroup>(
+    num_candidates: usize,
+    defeat_groups: &[DefeatGroup],
+) -> Box<[Box<[usize]>]>
+where
+    DefeatGroup: Borrow<[(usize, usize)]>,
+{
+    let mut beats = vec![Vec::new(); num_candidates];
+    for defeat_group in defeat_groups {
+        // Defeats of equal strength are locked simultaneously, so that the
+        // result does not depend on their order: any of them that would lie
+        // on a cycle together with the locked and equally strong defeats is
+        // skipped.
+        let mut tentative = beats.clone();
+        for &(a, b) in defeat_group.borrow() {
+            tentative[a].push(b);
+        }
+        let locked = defeat_group
+            .borrow()
+            .iter()
+            .filter(|&&(a, b)| !reaches(&tentative, b, a))
+            .cloned()
+            .collect::<Vec<_>>();
+        for (a, b) in locked {
+            beats[a].push(b);
+        }
+    }
+    beats
+        .into_iter()
+        .map(|v| v.into_boxed_slice())
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+// Ranks candidates in layers, each made up of the remaining candidates not
+// beaten by another remaining candidate.  beats must be acyclic.
+pub(crate) fn layered_ranking<Beats>(beats: &[Beats]) -> Box<[Box<[usize]>]>
+where
+    Beats: Borrow<[usize]>,
+{
+    let num_candidates = beats.len();
+    let mut ranking = Vec::new();
+    let mut remaining = vec![true; num_candidates];
+    let mut num_remaining = num_candidates;
+    while num_remaining != 0 {
+        let beaten = &mut vec![false; num_candidates][..];
+        for a in (0..num_candidates).filter(|&a| remaining[a]) {
+            for &b in beats[a].borrow() {
+                beaten[b] = true;
+            }
+        }
+        let winners = (0..num_candidates)
+            .filter(|&c| remaining[c] && !beaten[c])
+            .collect::<Vec<_>>();
+        debug_assert!(!winners.is_empty());
+        for &c in &winners {
+            remaining[c] = false;
+        }
+        num_remaining -= winners.len();
+        ranking.push(winners.into_boxed_slice());
+    }
+    ranking.into_boxed_slice()
+}
+
+pub fn ranked_pairs_ranking<DefeatGroup>(
+    num_candidates: usize,
+    defeat_groups: &[DefeatGroup],
+) -> Box<[Box<[usize]>]>
+where
+    DefeatGroup: Borrow<[(usize, usize)]>,
+{
+    layered_ranking(&ranked_pairs_locked(num_candidates, defeat_groups))
+}
+
+pub fn ranked_pairs<DefeatGroup>(
+    num_candidates: usize,
+    defeat_groups: &[DefeatGroup],
+) -> Box<[usize]>
+where
+    DefeatGroup: Borrow<[(usize, usize)]>,
+{
+    ranked_pairs_ranking(num_candidates, defeat_groups)
+        .first()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use pairwise::{pairwise_defeat_groups, pairwise_matrix, DefeatStrength};
+    use super::{ranked_pairs, ranked_pairs_locked, ranked_pairs_ranking};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_ranked_pairs_1() {
+        // Wikipedia
+        let (m, n, c, k) = (0, 1, 2, 3);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[m], &[n], &[c], &[k]], Q(42)),
+            (&[&[n], &[c], &[k], &[m]], Q(26)),
+            (&[&[c], &[k], &[n], &[m]], Q(15)),
+            (&[&[k], &[c], &[n], &[m]], Q(17)),
+        ];
+        let d = pairwise_matrix(4, ballots);
+        let defeat_groups = pairwise_defeat_groups(&d, DefeatStrength::WinningVotes);
+        assert_eq!(*ranked_pairs(4, &defeat_groups), [n]);
+        let expected: &[Box<[usize]>] =
+            &[Box::new([n]), Box::new([c]), Box::new([k]), Box::new([m])];
+        assert_eq!(*ranked_pairs_ranking(4, &defeat_groups), *expected);
+    }
+
+    #[test]
+    fn test_ranked_pairs_2() {
+        // An equally strong cycle is skipped entirely, leaving the weaker
+        // defeats of 3 to decide.
+        let defeat_groups: &[&[(usize, usize)]] = &[&[(0, 1), (1, 2), (2, 0)], &[(0, 3)]];
+        let expected: &[Box<[usize]>] = &[Box::new([3]), Box::new([]), Box::new([]), Box::new([])];
+        assert_eq!(*ranked_pairs_locked(4, defeat_groups), *expected);
+        let expected: &[Box<[usize]>] = &[Box::new([0, 1, 2]), Box::new([3])];
+        assert_eq!(*ranked_pairs_ranking(4, defeat_groups), *expected);
+    }
+
+    #[test]
+    fn test_ranked_pairs_3() {
+        let defeat_groups: &[&[(usize, usize)]] = &[&[(0, 1)], &[(1, 2)], &[(2, 0)]];
+        let expected: &[Box<[usize]>] = &[Box::new([0]), Box::new([1]), Box::new([2])];
+        assert_eq!(*ranked_pairs_ranking(3, defeat_groups), *expected);
+    }
+}
@@ -1,6 +1,32 @@
 use std::borrow::Borrow;
+use std::cmp::{max, min};
 use std::vec::Vec;
+use pairwise::{defeat_strengths, DefeatStrength};
 use schwartz_set::schwartz_set;
+use traits::{Weight, WeightOps};
+use util::combine_dups;
+
+pub fn group_defeats<W: Weight>(
+    mut defeats: Vec<(&W, (usize, usize))>,
+) -> Vec<Box<[(usize, usize)]>> {
+    defeats.sort_by(|a, b| b.0.cmp(a.0));
+    combine_dups(
+        defeats,
+        |a, b| a.0.fuzzy_eq(b.0),
+        |a| vec![a],
+        |mut a, b| {
+            a.push(b);
+            a
+        },
+    ).iter()
+        .map(|a| {
+            a.iter()
+                .map(|&(_, g)| g)
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect()
+}
 
 pub fn schulze_graph<DefeatGroup>(
     num_candidates: usize,
@@ -9,13 +35,31 @@ pub fn schulze_graph<DefeatGroup>(
 where
     DefeatGroup: Borrow<[(usize, usize)]>,
 {
+    let candidates = (0..num_candidates).collect::<Vec<_>>();
+    schulze_subgraph(num_candidates, &candidates, defeat_groups)
+}
+
+fn schulze_subgraph<DefeatGroup>(
+    num_candidates: usize,
+    candidates: &[usize],
+    defeat_groups: &[DefeatGroup],
+) -> Box<[usize]>
+where
+    DefeatGroup: Borrow<[(usize, usize)]>,
+{
+    let included = &mut vec![false; num_candidates][..];
+    for &c in candidates {
+        included[c] = true;
+    }
     let defeaters = &mut vec![Vec::new(); num_candidates][..];
     for defeat_group in defeat_groups {
         for &(a, b) in defeat_group.borrow() {
-            defeaters[b].push(a);
+            if included[a] && included[b] {
+                defeaters[b].push(a);
+            }
         }
     }
-    let mut candidates = (0..num_candidates).collect::<Vec<_>>().into_boxed_slice();
+    let mut candidates = candidates.to_vec().into_boxed_slice();
     for defeat_group in defeat_groups.iter().rev() {
         if candidates.len() <= 1 {
             break;
@@ -23,16 +67,66 @@ where
         let schwartz = schwartz_set(&candidates, defeaters);
         candidates = schwartz;
         for &(a, b) in defeat_group.borrow().iter().rev() {
-            let a1 = defeaters[b].pop();
-            debug_assert_eq!(a1, Some(a));
+            if included[a] && included[b] {
+                let a1 = defeaters[b].pop();
+                debug_assert_eq!(a1, Some(a));
+            }
         }
     }
     candidates
 }
 
+pub fn schulze_ranking<DefeatGroup>(
+    num_candidates: usize,
+    defeat_groups: &[DefeatGroup],
+) -> Box<[Box<[usize]>]>
+where
+    DefeatGroup: Borrow<[(usize, usize)]>,
+{
+    let mut ranking = Vec::new();
+    let mut candidates = (0..num_candidates).collect::<Vec<_>>();
+    while !candidates.is_empty() {
+        let mut winners = schulze_subgraph(num_candidates, &candidates, defeat_groups);
+        winners.sort();
+        candidates.retain(|c| winners.binary_search(c).is_err());
+        ranking.push(winners);
+    }
+    ranking.into_boxed_slice()
+}
+
+pub fn path_strengths<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[Box<[W]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let n = d.len();
+    let mut p = defeat_strengths(d, strength);
+    for k in 0..n {
+        for a in (0..n).filter(|&a| a != k) {
+            for b in (0..n).filter(|&b| b != k && b != a) {
+                let through = min(&p[a][k], &p[k][b]).clone();
+                p[a][b] = max(p[a][b].clone(), through);
+            }
+        }
+    }
+    p
+}
+
+pub fn path_winners<W: Weight>(p: &[Box<[W]>]) -> Box<[usize]> {
+    (0..p.len())
+        .filter(|&a| (0..p.len()).all(|b| p[a][b] >= p[b][a]))
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::schulze_graph;
+    use gmp::mpq::Mpq;
+
+    use pairwise::DefeatStrength;
+    use super::{path_strengths, path_winners, schulze_graph, schulze_ranking};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
 
     #[test]
     fn test_schulze_1() {
@@ -51,4 +145,58 @@ mod tests {
         ];
         assert_eq!(*schulze_graph(5, defeat_groups), [4]);
     }
+
+    #[test]
+    fn test_schulze_ranking_1() {
+        // Wikipedia
+        let defeat_groups: &[&[(usize, usize)]] = &[
+            &[(1, 3)],
+            &[(4, 3)],
+            &[(0, 3)],
+            &[(2, 1)],
+            &[(3, 2)],
+            &[(4, 1)],
+            &[(0, 2)],
+            &[(1, 0)],
+            &[(2, 4)],
+            &[(4, 0)],
+        ];
+        let expected: &[Box<[usize]>] = &[
+            Box::new([4]),
+            Box::new([0]),
+            Box::new([2]),
+            Box::new([1]),
+            Box::new([3]),
+        ];
+        assert_eq!(*schulze_ranking(5, defeat_groups), *expected);
+    }
+
+    #[test]
+    fn test_schulze_ranking_2() {
+        let defeat_groups: &[&[(usize, usize)]] = &[&[(0, 1), (0, 2)], &[(1, 3), (2, 3)]];
+        let expected: &[Box<[usize]>] = &[Box::new([0]), Box::new([1, 2]), Box::new([3])];
+        assert_eq!(*schulze_ranking(4, defeat_groups), *expected);
+    }
+
+    #[test]
+    fn test_path_strengths_1() {
+        // Wikipedia
+        let d: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(20), Q(26), Q(30), Q(22)]),
+            Box::new([Q(25), Q(0), Q(16), Q(33), Q(18)]),
+            Box::new([Q(19), Q(29), Q(0), Q(17), Q(24)]),
+            Box::new([Q(15), Q(12), Q(28), Q(0), Q(14)]),
+            Box::new([Q(23), Q(27), Q(21), Q(31), Q(0)]),
+        ];
+        let expected: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(28), Q(28), Q(30), Q(24)]),
+            Box::new([Q(25), Q(0), Q(28), Q(33), Q(24)]),
+            Box::new([Q(25), Q(29), Q(0), Q(29), Q(24)]),
+            Box::new([Q(25), Q(28), Q(28), Q(0), Q(24)]),
+            Box::new([Q(25), Q(28), Q(28), Q(31), Q(0)]),
+        ];
+        let p = path_strengths(d, DefeatStrength::WinningVotes);
+        assert_eq!(*p, *expected);
+        assert_eq!(*path_winners(&p), [4]);
+    }
 }
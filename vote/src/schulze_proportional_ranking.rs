@@ -0,0 +1,109 @@
+use std::borrow::Borrow;
+use std::vec::Vec;
+
+use schulze::{group_defeats, schulze_graph};
+use schulze_stv::set_strengths;
+use traits::{Weight, WeightOps};
+
+fn extend_rankings<W, Group, Groups>(
+    num_candidates: usize,
+    num_positions: usize,
+    ballots: &[(Groups, W)],
+    prefix: &mut Vec<usize>,
+    rankings: &mut Vec<Box<[usize]>>,
+) where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    if prefix.len() == num_positions {
+        rankings.push(prefix.clone().into_boxed_slice());
+        return;
+    }
+
+    let remaining = (0..num_candidates)
+        .filter(|c| !prefix.contains(c))
+        .collect::<Vec<_>>();
+    let strengths = remaining
+        .iter()
+        .map(|&c| {
+            let mut set = prefix.clone();
+            set.push(c);
+            set.sort();
+            set_strengths(num_candidates, &set, ballots)
+        })
+        .collect::<Vec<_>>();
+
+    let defeats = (0..remaining.len())
+        .flat_map(|i| {
+            (0..remaining.len())
+                .filter(move |&j| j != i)
+                .map(move |j| (i, j))
+        })
+        .map(|(i, j)| (&strengths[i][remaining[j]], (i, j)))
+        .collect::<Vec<_>>();
+
+    for i in schulze_graph(remaining.len(), &group_defeats(defeats)).iter() {
+        prefix.push(remaining[*i]);
+        extend_rankings(num_candidates, num_positions, ballots, prefix, rankings);
+        prefix.pop();
+    }
+}
+
+pub fn schulze_proportional_ranking<W, Group, Groups>(
+    num_candidates: usize,
+    num_positions: usize,
+    ballots: &[(Groups, W)],
+) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let mut rankings = Vec::new();
+    extend_rankings(
+        num_candidates,
+        num_positions,
+        ballots,
+        &mut Vec::with_capacity(num_positions),
+        &mut rankings,
+    );
+    rankings.sort();
+    rankings.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use schulze_stv::schulze_stv;
+    use super::schulze_proportional_ranking;
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_schulze_proportional_ranking_1() {
+        // Wikipedia
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[0], &[1], &[2]], Q(12)),
+            (&[&[0], &[2], &[1]], Q(26)),
+            (&[&[0], &[2], &[1]], Q(12)),
+            (&[&[2], &[0], &[1]], Q(13)),
+            (&[&[1]], Q(27)),
+        ];
+        let expected: &[Box<[usize]>] = &[Box::new([0, 1, 2])];
+        assert_eq!(*schulze_proportional_ranking(3, 3, ballots), *expected);
+        let mut prefix = schulze_proportional_ranking(3, 2, ballots)[0].clone();
+        prefix.sort();
+        assert_eq!(*schulze_stv(3, 2, ballots), [prefix]);
+    }
+
+    #[test]
+    fn test_schulze_proportional_ranking_2() {
+        let ballots: &[(&[&[usize]], Mpq)] = &[(&[&[0], &[1]], Q(1)), (&[&[1], &[0]], Q(1))];
+        let expected: &[Box<[usize]>] = &[Box::new([0, 1]), Box::new([1, 0])];
+        assert_eq!(*schulze_proportional_ranking(2, 2, ballots), *expected);
+    }
+}
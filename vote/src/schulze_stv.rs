@@ -2,11 +2,10 @@ use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::iter::once;
 use std::vec::Vec;
-use util::combine_dups;
 
 use combination::{decode_combination, encode_combination, make_binomial};
 use proportional_completion::proportional_completion;
-use schulze::schulze_graph;
+use schulze::{group_defeats, schulze_graph, schulze_ranking};
 use traits::{Weight, WeightOps};
 use vote_management::strength;
 
@@ -70,6 +69,46 @@ fn replacements(set: &[usize], opponent: usize) -> Box<[Box<[usize]>]> {
         .into_boxed_slice()
 }
 
+pub(crate) fn set_strengths<W, Group, Groups>(
+    num_candidates: usize,
+    set: &[usize],
+    ballots: &[(Groups, W)],
+) -> Box<[W]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let num_seats = set.len();
+    let seti = &mut vec![!0; num_candidates][..];
+    for (i, &c) in set.iter().enumerate() {
+        seti[c] = i;
+    }
+    (0..num_candidates)
+        .map(|opponent| if seti[opponent] != !0 {
+            W::zero()
+        } else {
+            let patterns = ballots
+                .iter()
+                .map(|&(ref groups, ref w)| {
+                    (preferred(num_seats, seti, opponent, groups.borrow()), w)
+                })
+                .collect::<Vec<_>>();
+            let completed =
+                proportional_completion(patterns.iter().map(|&(ref a, w)| (&a[..], w)));
+            strength(
+                num_seats,
+                &completed
+                    .iter()
+                    .map(|&(ref a, ref w)| (&**a, (*w).clone()))
+                    .collect::<Vec<_>>()[..],
+            )
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
 fn all_strengths<W, Group, Groups>(
     num_candidates: usize,
     num_seats: usize,
@@ -87,42 +126,17 @@ where
     (0..num_combinations)
         .map(|m| {
             let set = decode_combination(&binomial, num_seats, m);
-            let seti = &mut vec![!0; num_candidates][..];
-            for (i, &c) in set.iter().enumerate() {
-                seti[c] = i;
-            }
-            (0..num_candidates)
-                .map(|opponent| if seti[opponent] != !0 {
-                    W::zero()
-                } else {
-                    let patterns = ballots
-                        .iter()
-                        .map(|&(ref groups, ref w)| {
-                            (preferred(num_seats, seti, opponent, groups.borrow()), w)
-                        })
-                        .collect::<Vec<_>>();
-                    let completed =
-                        proportional_completion(patterns.iter().map(|&(ref a, w)| (&a[..], w)));
-                    strength(
-                        num_seats,
-                        &completed
-                            .iter()
-                            .map(|&(ref a, ref w)| (&**a, (*w).clone()))
-                            .collect::<Vec<_>>()[..],
-                    )
-                })
-                .collect::<Vec<_>>()
-                .into_boxed_slice()
+            set_strengths(num_candidates, &set, ballots)
         })
         .collect::<Vec<_>>()
         .into_boxed_slice()
 }
 
-pub fn schulze_stv<W, Group, Groups>(
+fn defeat_groups<W, Group, Groups>(
     num_candidates: usize,
     num_seats: usize,
     ballots: &[(Groups, W)],
-) -> Box<[Box<[usize]>]>
+) -> Vec<Box<[(usize, usize)]>>
 where
     W: Weight,
     for<'w> &'w W: WeightOps<W>,
@@ -132,7 +146,7 @@ where
     let binomial = &make_binomial(num_candidates, num_seats);
     let strengths = all_strengths(num_candidates, num_seats, ballots);
 
-    let mut defeats = strengths
+    let defeats = strengths
         .iter()
         .enumerate()
         .flat_map(move |(m, strength)| {
@@ -155,37 +169,61 @@ where
         })
         .collect::<Vec<_>>();
 
-    defeats.sort_by(|a, b| b.0.cmp(a.0));
-    let defeat_groups = &combine_dups(
-        defeats,
-        |a, b| a.0.fuzzy_eq(b.0),
-        |a| vec![a],
-        |mut a, b| {
-            a.push(b);
-            a
-        },
-    ).iter()
-        .map(|a| {
-            a.iter()
-                .map(|&(_, g)| g)
-                .collect::<Vec<_>>()
-                .into_boxed_slice()
-        })
-        .collect::<Vec<_>>();
+    group_defeats(defeats)
+}
 
-    schulze_graph(strengths.len(), defeat_groups)
+pub fn schulze_stv<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let binomial = &make_binomial(num_candidates, num_seats);
+    let defeat_groups = defeat_groups(num_candidates, num_seats, ballots);
+
+    schulze_graph(binomial[num_candidates][num_seats], &defeat_groups)
         .iter()
         .map(move |&c| decode_combination(binomial, num_seats, c))
         .collect::<Vec<_>>()
         .into_boxed_slice()
 }
 
+pub fn schulze_stv_ranking<W, Group, Groups>(
+    num_candidates: usize,
+    num_seats: usize,
+    ballots: &[(Groups, W)],
+) -> Vec<Box<[Box<[usize]>]>>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+    Group: Borrow<[usize]>,
+    Groups: Borrow<[Group]>,
+{
+    let binomial = &make_binomial(num_candidates, num_seats);
+    let defeat_groups = defeat_groups(num_candidates, num_seats, ballots);
+
+    schulze_ranking(binomial[num_candidates][num_seats], &defeat_groups)
+        .iter()
+        .map(|sets| {
+            sets.iter()
+                .map(|&c| decode_combination(binomial, num_seats, c))
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use gmp::mpq::Mpq;
 
     use combination::{encode_combination, make_binomial};
-    use super::{all_strengths, replacements, schulze_stv};
+    use super::{all_strengths, replacements, schulze_stv, schulze_stv_ranking};
 
     const Q: fn(i64) -> Mpq = Mpq::from;
 
@@ -276,4 +314,28 @@ mod tests {
         let expected: &[Box<[usize]>] = &[Box::new([0, 1])];
         assert_eq!(*schulze_stv(3, 2, ballots), *expected);
     }
+
+    #[test]
+    fn test_schulze_stv_ranking_1() {
+        // Wikipedia
+        let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+        let ballots: &[(&[&[usize]], Mpq)] = &[
+            (&[&[a], &[c], &[b], &[e], &[d]], Q(5)),
+            (&[&[a], &[d], &[e], &[c], &[b]], Q(5)),
+            (&[&[b], &[e], &[d], &[a], &[c]], Q(8)),
+            (&[&[c], &[a], &[b], &[e], &[d]], Q(3)),
+            (&[&[c], &[a], &[e], &[b], &[d]], Q(7)),
+            (&[&[c], &[b], &[a], &[d], &[e]], Q(2)),
+            (&[&[d], &[c], &[e], &[b], &[a]], Q(7)),
+            (&[&[e], &[b], &[a], &[d], &[c]], Q(8)),
+        ];
+        let expected: Vec<Box<[Box<[usize]>]>> = vec![
+            Box::new([Box::new([e])]),
+            Box::new([Box::new([a])]),
+            Box::new([Box::new([c])]),
+            Box::new([Box::new([b])]),
+            Box::new([Box::new([d])]),
+        ];
+        assert_eq!(schulze_stv_ranking(5, 1, ballots), expected);
+    }
 }
@@ -0,0 +1,95 @@
+use std::vec::Vec;
+
+use pairwise::{defeat_strengths, DefeatStrength};
+use ranked_pairs::layered_ranking;
+use schulze::path_strengths;
+use traits::{Weight, WeightOps};
+
+// a defeats b when their pairwise defeat is stronger than the weakest defeat
+// on every cycle through it, i.e. than the strongest path from b back to a.
+pub fn split_cycle_defeats<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    let s = defeat_strengths(d, strength);
+    let p = path_strengths(d, strength);
+    (0..d.len())
+        .map(|a| {
+            (0..d.len())
+                .filter(|&b| !s[a][b].is_zero() && s[a][b] > p[b][a])
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+pub fn split_cycle_ranking<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[Box<[usize]>]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    layered_ranking(&split_cycle_defeats(d, strength))
+}
+
+pub fn split_cycle<W>(d: &[Box<[W]>], strength: DefeatStrength) -> Box<[usize]>
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    split_cycle_ranking(d, strength)
+        .first()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use gmp::mpq::Mpq;
+
+    use pairwise::DefeatStrength;
+    use super::{split_cycle, split_cycle_defeats, split_cycle_ranking};
+
+    const Q: fn(i64) -> Mpq = Mpq::from;
+
+    #[test]
+    fn test_split_cycle_1() {
+        // Wikipedia
+        let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+        let m: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(20), Q(26), Q(30), Q(22)]),
+            Box::new([Q(25), Q(0), Q(16), Q(33), Q(18)]),
+            Box::new([Q(19), Q(29), Q(0), Q(17), Q(24)]),
+            Box::new([Q(15), Q(12), Q(28), Q(0), Q(14)]),
+            Box::new([Q(23), Q(27), Q(21), Q(31), Q(0)]),
+        ];
+        let expected: &[Box<[usize]>] = &[
+            Box::new([c, d]),
+            Box::new([d]),
+            Box::new([b]),
+            Box::new([]),
+            Box::new([b, d]),
+        ];
+        assert_eq!(*split_cycle_defeats(m, DefeatStrength::Margins), *expected);
+        assert_eq!(*split_cycle(m, DefeatStrength::Margins), [a, e]);
+        let expected: &[Box<[usize]>] = &[
+            Box::new([a, e]),
+            Box::new([c]),
+            Box::new([b]),
+            Box::new([d]),
+        ];
+        assert_eq!(*split_cycle_ranking(m, DefeatStrength::Margins), *expected);
+    }
+
+    #[test]
+    fn test_split_cycle_2() {
+        // A cycle of equally strong defeats leaves everyone undefeated.
+        let m: &[Box<[Mpq]>] = &[
+            Box::new([Q(0), Q(2), Q(1)]),
+            Box::new([Q(1), Q(0), Q(2)]),
+            Box::new([Q(2), Q(1), Q(0)]),
+        ];
+        assert_eq!(*split_cycle(m, DefeatStrength::Margins), [0, 1, 2]);
+    }
+}
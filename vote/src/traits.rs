@@ -37,6 +37,8 @@ where
 pub trait Weight: Clone + Ord + WeightOps<Self> + Zero + One + fmt::Debug {
     fn from_i64(i64) -> Self;
 
+    fn floor(&self) -> Self;
+
     #[inline]
     fn fuzzy_eq(&self, other: &Self) -> bool {
         self == other
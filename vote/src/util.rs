@@ -1,5 +1,27 @@
 use std::cmp::Ordering;
 
+use traits::{Weight, WeightOps};
+
+pub fn decimal_scale<W: Weight>(decimals: u32) -> W {
+    (0..decimals).fold(W::one(), |acc, _| acc * W::from_i64(10))
+}
+
+pub fn truncate<W>(x: &W, scale: &W) -> W
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    (x * scale).floor() / scale
+}
+
+pub fn round_up<W>(x: &W, scale: &W) -> W
+where
+    W: Weight,
+    for<'w> &'w W: WeightOps<W>,
+{
+    W::zero() - truncate(&(W::zero() - x), scale)
+}
+
 pub fn combine_dups<A, B, I, Eq, One, More>(i: I, eq: Eq, one: One, more: More) -> Vec<B>
 where
     I: IntoIterator<Item = A>,
@@ -7,4 +7,9 @@ impl Weight for Mpq {
     fn from_i64(n: i64) -> Mpq {
         Mpq::from(n)
     }
+
+    #[inline]
+    fn floor(&self) -> Mpq {
+        Mpq::from(Mpq::floor(self))
+    }
 }
@@ -13,4 +13,9 @@ where
     fn from_i64(n: i64) -> Ratio<T> {
         Ratio::from_integer(n.into())
     }
+
+    #[inline]
+    fn floor(&self) -> Ratio<T> {
+        Ratio::floor(self)
+    }
 }